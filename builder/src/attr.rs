@@ -0,0 +1,564 @@
+use proc_macro2::*;
+use quote::*;
+use syn::meta::ParseNestedMeta;
+use syn::*;
+
+// how the generated setters and `build` take the builder
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pattern {
+    // setters `&mut self -> &mut Self`, `build(&mut self)` moves the values out
+    #[default]
+    Mutable,
+    // setters `&mut self -> &mut Self`, `build(&self)` clones the values
+    Immutable,
+    // setters `self -> Self`, `build(self)` consumes the builder, `try_build(self)` hands it
+    // back when it fails
+    Owned,
+}
+
+impl Pattern {
+    pub fn setter_receiver(self) -> TokenStream {
+        match self {
+            Pattern::Mutable | Pattern::Immutable => quote!(&mut self),
+            Pattern::Owned => quote!(mut self),
+        }
+    }
+
+    pub fn setter_output(self) -> TokenStream {
+        match self {
+            Pattern::Mutable | Pattern::Immutable => quote!(&mut Self),
+            Pattern::Owned => quote!(Self),
+        }
+    }
+
+    pub fn build_receiver(self) -> TokenStream {
+        match self {
+            Pattern::Mutable => quote!(&mut self),
+            Pattern::Immutable => quote!(&self),
+            Pattern::Owned => quote!(self),
+        }
+    }
+
+    // the value of a builder field as `build` gets it
+    pub fn take_field(self, ident: &Ident) -> TokenStream {
+        match self {
+            Pattern::Mutable => quote!(::core::mem::take(&mut self.#ident)),
+            Pattern::Immutable => quote!(::core::clone::Clone::clone(&self.#ident)),
+            Pattern::Owned => quote!(self.#ident),
+        }
+    }
+}
+
+// where the generated `Vec`, `String` and `format!` come from
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrateRoot {
+    #[default]
+    Std,
+    // `#[builder(crate_root = alloc)]`, for `no_std` crates with `extern crate alloc`
+    Alloc,
+}
+
+impl CrateRoot {
+    pub fn path(self) -> TokenStream {
+        match self {
+            CrateRoot::Std => quote!(::std),
+            CrateRoot::Alloc => quote!(::alloc),
+        }
+    }
+}
+
+// `#[builder(..)]` on the struct
+#[derive(Default)]
+pub struct StructAttrs {
+    pub pattern: Pattern,
+    // `#[builder(typestate)]`, required fields are tracked in the type of the builder
+    pub typestate: Option<Ident>,
+    // `#[builder(setter(..))]`, defaults for the setters of every field
+    pub setter: SetterAttrs,
+    pub build_fn: BuildFnAttrs,
+    // `#[builder(name = "CmdOpts")]` instead of `XxxBuilder`
+    pub name: Option<Ident>,
+    // `#[builder(vis = "pub(crate)")]` instead of the visibility of the struct
+    pub vis: Option<Visibility>,
+    // `#[builder(derive(Clone, Debug))]` on the builder
+    pub derives: Vec<Path>,
+    // `#[builder(debug)]`, a `Debug` impl telling set and unset fields apart
+    pub debug: Option<Ident>,
+    pub crate_root: CrateRoot,
+    // `#[builder(to_builder)]`, `From<T>` for the builder and `T::to_builder()`
+    pub to_builder: Option<Ident>,
+}
+
+// `#[builder(build_fn(validate = path, validate_built = path))]`
+#[derive(Default)]
+pub struct BuildFnAttrs {
+    // `fn(&XxxBuilder) -> Result<(), E>`, called before anything is taken from the builder
+    pub validate: Option<Path>,
+    // `fn(&Xxx) -> Result<(), E>`, called with the built value
+    pub validate_built: Option<Path>,
+}
+
+impl BuildFnAttrs {
+    // `seen` spans every `build_fn(..)` of the item, they may be split across attributes
+    fn parse(&mut self, meta: &ParseNestedMeta, seen: &mut SeenKeys) -> Result<()> {
+        meta.parse_nested_meta(|meta| {
+            seen.insert(&meta)?;
+            if meta.path.is_ident("validate") {
+                self.validate = Some(parse_path_value(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("validate_built") {
+                self.validate_built = Some(parse_path_value(&meta)?);
+                Ok(())
+            } else {
+                Err(unknown_key(&meta, &["validate", "validate_built"], "expected `validate` or `validate_built`"))
+            }
+        })
+    }
+}
+
+impl StructAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        Self::from_layers(&[attrs])
+    }
+
+    // the attributes of an enum then those of a variant, a later layer may set a key again
+    pub fn from_layers(layers: &[&[Attribute]]) -> Result<Self> {
+        let mut struct_attrs = StructAttrs::default();
+        let mut pattern_ident = None;
+        for attrs in layers {
+            let mut seen = SeenKeys::default();
+            let mut seen_setter = SeenKeys::default();
+            let mut seen_build_fn = SeenKeys::default();
+            for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+                attr.parse_nested_meta(|meta| {
+                    if !meta.path.is_ident("setter") && !meta.path.is_ident("build_fn") {
+                        seen.insert(&meta)?;
+                    }
+                    if meta.path.is_ident("pattern") {
+                        // `#[builder(pattern = "owned")]`, `#[builder(pattern = owned)]`
+                        let pattern = parse_ident_value(&meta)?;
+                        struct_attrs.pattern = if pattern == "mutable" {
+                            Pattern::Mutable
+                        } else if pattern == "immutable" {
+                            Pattern::Immutable
+                        } else if pattern == "owned" {
+                            Pattern::Owned
+                        } else {
+                            return Err(Error::new_spanned(
+                                pattern,
+                                "expected `mutable`, `immutable` or `owned`",
+                            ));
+                        };
+                        pattern_ident = Some(pattern);
+                        Ok(())
+                    } else if meta.path.is_ident("typestate") {
+                        struct_attrs.typestate = meta.path.get_ident().cloned();
+                        Ok(())
+                    } else if meta.path.is_ident("setter") {
+                        struct_attrs.setter.parse(&meta, &mut seen_setter)?;
+                        match struct_attrs.setter.name {
+                            Some(ref name) => Err(Error::new_spanned(
+                                name,
+                                "setter(name) only applies to a field, try setter(prefix)",
+                            )),
+                            None => Ok(()),
+                        }
+                    } else if meta.path.is_ident("build_fn") {
+                        struct_attrs.build_fn.parse(&meta, &mut seen_build_fn)
+                    } else if meta.path.is_ident("name") {
+                        struct_attrs.name = Some(parse_ident_value(&meta)?);
+                        Ok(())
+                    } else if meta.path.is_ident("vis") {
+                        let value = meta.value()?;
+                        struct_attrs.vis = Some(if value.peek(LitStr) {
+                            value.parse::<LitStr>()?.parse()?
+                        } else {
+                            value.parse()?
+                        });
+                        Ok(())
+                    } else if meta.path.is_ident("to_builder") {
+                        struct_attrs.to_builder = meta.path.get_ident().cloned();
+                        Ok(())
+                    } else if meta.path.is_ident("debug") {
+                        struct_attrs.debug = meta.path.get_ident().cloned();
+                        Ok(())
+                    } else if meta.path.is_ident("crate_root") {
+                        // `#[builder(crate_root = alloc)]`, `#[builder(crate_root = "std")]`
+                        let root = parse_ident_value(&meta)?;
+                        struct_attrs.crate_root = if root == "std" {
+                            CrateRoot::Std
+                        } else if root == "alloc" {
+                            CrateRoot::Alloc
+                        } else {
+                            return Err(Error::new_spanned(root, "expected `std` or `alloc`"));
+                        };
+                        Ok(())
+                    } else if meta.path.is_ident("derive") {
+                        meta.parse_nested_meta(|meta| {
+                            if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                                return Err(meta.error("expected a trait to derive"));
+                            }
+                            if meta.path.is_ident("Default") {
+                                return Err(Error::new_spanned(
+                                    &meta.path,
+                                    "`Default` is already implemented for the builder, unless a field is `start_fn`",
+                                ));
+                            }
+                            struct_attrs.derives.push(meta.path);
+                            Ok(())
+                        })
+                    } else {
+                        Err(unknown_builder_key(&meta, STRUCT_KEYS, FIELD_KEYS, "a struct attribute, it goes on a field"))
+                    }
+                })?;
+            }
+        }
+        if let Some(debug) = &struct_attrs.debug
+            && struct_attrs
+                .derives
+                .iter()
+                .any(|path| path.segments.last().is_some_and(|s| s.ident == "Debug"))
+        {
+            return Err(Error::new_spanned(debug, "`debug` and `derive(Debug)` both implement `Debug`"));
+        }
+        if struct_attrs.typestate.is_some() {
+            // every setter of a typestate builder changes its type, so it has to be moved
+            if let Some(pattern) = pattern_ident
+                && struct_attrs.pattern != Pattern::Owned
+            {
+                return Err(Error::new_spanned(
+                    pattern,
+                    "typestate builders always use the `owned` pattern",
+                ));
+            }
+            struct_attrs.pattern = Pattern::Owned;
+        }
+        // `build(&mut self)` has moved every value out before the built value can be checked
+        if let Some(validate_built) = &struct_attrs.build_fn.validate_built
+            && struct_attrs.pattern == Pattern::Mutable
+        {
+            return Err(Error::new_spanned(
+                validate_built,
+                "validate_built would leave a `mutable` builder empty when it fails, \
+                 use `validate` or the `immutable` or `owned` pattern",
+            ));
+        }
+        Ok(struct_attrs)
+    }
+}
+
+// `#[builder(..)]` on a field
+#[derive(Default)]
+pub struct FieldAttrs {
+    // `#[builder(each = "arg")]`, `#[builder(each = arg)]`
+    pub each: Option<Ident>,
+    pub default: Option<FieldDefault>,
+    // `#[builder(name = "host")]` names the setter of a positional field
+    pub name: Option<Ident>,
+    pub setter: SetterAttrs,
+    // `#[builder(skip)]`, no setter, always built from the default
+    pub skip: bool,
+    // `#[builder(sub_builder)]`, the field type derives `Builder` too
+    pub sub_builder: Option<Ident>,
+    // `#[builder(sub_builder = "TlsOpts")]`, for an inner builder with its own `name`
+    pub sub_builder_ty: Option<Path>,
+    // `#[builder(merge = "replace")]` on an `each` field
+    pub merge: Option<(Ident, MergePolicy)>,
+    // `#[builder(env = "APP_PORT")]`, parsed with `FromStr` when the setter wasn't called
+    pub env: Option<LitStr>,
+    // `#[builder(group = "auth", exclusive)]`, at most one field of the group is set
+    // `#[builder(group = "auth", required)]`, exactly one is
+    pub group: Option<(Ident, LitStr)>,
+    pub exclusive: bool,
+    pub required: bool,
+    // `#[builder(start_fn)]`, a parameter of `builder(..)` instead of a setter
+    pub start_fn: Option<Ident>,
+    // `#[builder(redact)]`, hidden by `#[builder(debug)]`
+    pub redact: bool,
+}
+
+// how `merge` combines an `each` field set on both builders
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    // the elements of the other builder are added
+    #[default]
+    Append,
+    // the collection of the other builder wins, like any other field
+    Replace,
+}
+
+// `setter(into, try_into, name = "with_exec", prefix = "with_")`
+#[derive(Default, Clone)]
+pub struct SetterAttrs {
+    // setters take `impl Into<T>`
+    pub into: bool,
+    // also generate `try_x(v: impl TryInto<T>)`
+    pub try_into: bool,
+    // the setter of this field, field level only
+    pub name: Option<Ident>,
+    // put before the field name to name its setter
+    pub prefix: Option<String>,
+}
+
+impl SetterAttrs {
+    // `seen` spans every `setter(..)` of the item, they may be split across attributes
+    fn parse(&mut self, meta: &ParseNestedMeta, seen: &mut SeenKeys) -> Result<()> {
+        meta.parse_nested_meta(|meta| {
+            seen.insert(&meta)?;
+            if meta.path.is_ident("into") {
+                self.into = true;
+                Ok(())
+            } else if meta.path.is_ident("try_into") {
+                self.try_into = true;
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                self.name = Some(parse_ident_value(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("prefix") {
+                let value = meta.value()?;
+                self.prefix = Some(if value.peek(LitStr) {
+                    value.parse::<LitStr>()?.value()
+                } else {
+                    value.parse::<Ident>()?.to_string()
+                });
+                Ok(())
+            } else {
+                Err(unknown_key(
+                    &meta,
+                    &["into", "try_into", "name", "prefix"],
+                    "expected `into`, `try_into`, `name` or `prefix`",
+                ))
+            }
+        })
+    }
+
+    // field level `setter(..)` adds to the struct level one
+    pub fn with(&self, field: &SetterAttrs) -> SetterAttrs {
+        SetterAttrs {
+            into: self.into || field.into,
+            try_into: self.try_into || field.try_into,
+            name: field.name.clone(),
+            prefix: field.prefix.clone().or_else(|| self.prefix.clone()),
+        }
+    }
+}
+
+pub enum FieldDefault {
+    // `#[builder(default)]`
+    Trait,
+    // `#[builder(default = expr)]`
+    Expr(Expr),
+}
+
+impl FieldDefault {
+    pub fn to_expr(&self) -> TokenStream {
+        match self {
+            FieldDefault::Trait => quote!(::core::default::Default::default()),
+            FieldDefault::Expr(expr) => expr.to_token_stream(),
+        }
+    }
+}
+
+impl FieldAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut field_attrs = FieldAttrs::default();
+        let mut group_flag = None;
+        let mut seen = SeenKeys::default();
+        let mut seen_setter = SeenKeys::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("setter") {
+                    seen.insert(&meta)?;
+                }
+                if meta.path.is_ident("each") {
+                    field_attrs.each = Some(parse_ident_value(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    field_attrs.default = Some(if meta.input.peek(Token![=]) {
+                        FieldDefault::Expr(meta.value()?.parse()?)
+                    } else {
+                        FieldDefault::Trait
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("name") {
+                    field_attrs.name = Some(parse_ident_value(&meta)?);
+                    Ok(())
+                } else if meta.path.is_ident("setter") {
+                    field_attrs.setter.parse(&meta, &mut seen_setter)
+                } else if meta.path.is_ident("skip") {
+                    field_attrs.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("merge") {
+                    let policy = parse_ident_value(&meta)?;
+                    let merge = if policy == "append" {
+                        MergePolicy::Append
+                    } else if policy == "replace" {
+                        MergePolicy::Replace
+                    } else {
+                        return Err(Error::new_spanned(policy, "expected `append` or `replace`"));
+                    };
+                    field_attrs.merge = Some((policy, merge));
+                    Ok(())
+                } else if meta.path.is_ident("group") {
+                    let key = meta.path.get_ident().cloned().unwrap();
+                    field_attrs.group = Some((key, meta.value()?.parse()?));
+                    Ok(())
+                } else if meta.path.is_ident("exclusive") {
+                    field_attrs.exclusive = true;
+                    group_flag = Some(meta.path);
+                    Ok(())
+                } else if meta.path.is_ident("required") {
+                    field_attrs.required = true;
+                    group_flag = Some(meta.path);
+                    Ok(())
+                } else if meta.path.is_ident("redact") {
+                    field_attrs.redact = true;
+                    Ok(())
+                } else if meta.path.is_ident("start_fn") {
+                    field_attrs.start_fn = meta.path.get_ident().cloned();
+                    Ok(())
+                } else if meta.path.is_ident("env") {
+                    field_attrs.env = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("sub_builder") {
+                    field_attrs.sub_builder = meta.path.get_ident().cloned();
+                    if meta.input.peek(Token![=]) {
+                        field_attrs.sub_builder_ty = Some(parse_path_value(&meta)?);
+                    }
+                    Ok(())
+                } else {
+                    // a misspelled `each` keeps the message 08-unrecognized-attribute.rs expects
+                    if did_you_mean(&meta.path, FIELD_KEYS) == Some("each") {
+                        Err(Error::new_spanned(&attr.meta, "expected `builder(each = \"...\")`"))
+                    } else {
+                        Err(unknown_builder_key(
+                            &meta,
+                            FIELD_KEYS,
+                            STRUCT_KEYS,
+                            "a field attribute, it goes on the struct, enum or variant",
+                        ))
+                    }
+                }
+            })?;
+        }
+        if let Some(flag) = group_flag
+            && field_attrs.group.is_none()
+        {
+            return Err(Error::new_spanned(flag, "expected a `group = \"...\"` for this field"));
+        }
+        Ok(field_attrs)
+    }
+}
+
+// the keys `#[builder(..)]` takes on a struct and on a field, for suggestions and errors
+const STRUCT_KEYS: &[&str] = &[
+    "pattern", "typestate", "setter", "build_fn", "name", "vis", "debug", "crate_root", "derive",
+    "to_builder",
+];
+const FIELD_KEYS: &[&str] = &[
+    "each", "default", "name", "setter", "skip", "merge", "group", "exclusive", "required", "redact",
+    "start_fn", "env", "sub_builder",
+];
+
+// the keys of all `#[builder(..)]` on one item, or of all its `setter(..)`
+#[derive(Default)]
+struct SeenKeys(Vec<Path>);
+
+impl SeenKeys {
+    fn insert(&mut self, meta: &ParseNestedMeta) -> Result<()> {
+        if self.0.contains(&meta.path) {
+            let key = meta.path.to_token_stream();
+            return Err(meta.error(format!("duplicate `{key}`")));
+        }
+        self.0.push(meta.path.clone());
+        Ok(())
+    }
+}
+
+// the known key closest to a misspelled one, if any is close enough
+fn did_you_mean<'a>(path: &Path, keys: &[&'a str]) -> Option<&'a str> {
+    let key = path.get_ident()?.to_string();
+    let (distance, closest) = keys
+        .iter()
+        .map(|known| (edit_distance(&key, known), *known))
+        .min()?;
+    (distance <= key.len().max(3) / 3).then_some(closest)
+}
+
+// suggests the closest of `keys`, or says what was `expected` when none is close
+fn unknown_key(meta: &ParseNestedMeta, keys: &[&str], expected: &str) -> Error {
+    match did_you_mean(&meta.path, keys) {
+        Some(closest) => misspelled_key(meta, closest),
+        None => meta.error(expected),
+    }
+}
+
+// a key of the other level says where it goes, an unknown one lists the `keys` of this level
+fn unknown_builder_key(meta: &ParseNestedMeta, keys: &[&str], other_keys: &[&str], misplaced: &str) -> Error {
+    let key = meta.path.to_token_stream();
+    if other_keys.iter().any(|other| meta.path.is_ident(other)) {
+        return meta.error(format!("`{key}` is not {misplaced}"));
+    }
+    if let Some(closest) = did_you_mean(&meta.path, keys) {
+        return misspelled_key(meta, closest);
+    }
+    let keys = keys.iter().map(|key| format!("`{key}`")).collect::<Vec<_>>().join(", ");
+    meta.error(format!("unknown builder attribute `{key}`, expected one of {keys}"))
+}
+
+fn misspelled_key(meta: &ParseNestedMeta, closest: &str) -> Error {
+    let key = meta.path.to_token_stream();
+    meta.error(format!("unknown builder attribute `{key}`, did you mean `{closest}`?"))
+}
+
+// Levenshtein distance, the number of characters to insert, delete or replace
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                diagonal.min(above).min(row[j]) + 1
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// `key = "ident"` or `key = ident`
+pub fn parse_ident_value(meta: &ParseNestedMeta) -> Result<Ident> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        let s: LitStr = value.parse()?;
+        match syn::parse_str::<Ident>(&s.value()) {
+            Ok(mut id) => {
+                id.set_span(s.span());
+                Ok(id)
+            }
+            Err(_) => Err(Error::new_spanned(s, "not a valid ident")),
+        }
+    } else if value.peek(Ident) {
+        value.parse()
+    } else if value.peek(Lit) {
+        Err(value.error("lit is not str"))
+    } else {
+        Err(value.error("not lit str nor ident"))
+    }
+}
+
+// `key = "path::to"` or `key = path::to`
+pub fn parse_path_value(meta: &ParseNestedMeta) -> Result<Path> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        let s: LitStr = value.parse()?;
+        s.parse()
+    } else {
+        value.parse()
+    }
+}
@@ -0,0 +1,527 @@
+use mylib_macro::*;
+use proc_macro2::*;
+use quote::*;
+use syn::ext::IdentExt;
+use syn::*;
+
+use crate::attr::*;
+
+pub enum FieldKind<'a> {
+    // `#[builder(each = "arg")] args: Vec<String>`, any `Extend + Default` collection
+    Each {
+        method: Ident,
+        // the collection, without `Option`
+        collection: &'a Type,
+        item: EachItem<'a>,
+        // `Option<Vec<T>>`, stays `None` until the first element is added
+        optional: bool,
+    },
+    // `Option<T>`, the setter takes `T`
+    Option(&'a Type),
+    // anything else, required unless it has a default
+    Plain,
+    // `#[builder(skip)]`, not in the builder at all
+    Skip,
+    // `#[builder(sub_builder)] server: Server`, holds a `ServerBuilder` with `&mut self` setters
+    SubBuilder(Type),
+}
+
+// what the element setter of an `each` field takes
+pub enum EachItem<'a> {
+    // `Vec<T>`, `VecDeque<T>`, `HashSet<T>`, ...
+    One(&'a Type),
+    // `HashMap<K, V>`, `BTreeMap<K, V>`, the setter takes `k` and `v`
+    Pair(&'a Type, &'a Type),
+    // other collections, `<C as IntoIterator>::Item`
+    Item,
+}
+
+pub struct BuilderField<'a> {
+    // the field in the struct
+    pub member: Member,
+    // names the setter and the slot in the builder
+    pub ident: Ident,
+    pub ty: &'a Type,
+    pub attrs: FieldAttrs,
+    pub kind: FieldKind<'a>,
+    // `#[cfg]` and `#[cfg_attr]`, repeated on everything generated for the field
+    pub cfgs: Vec<Attribute>,
+    // doc comments, repeated on its setters
+    pub docs: Vec<Attribute>,
+}
+
+impl<'a> BuilderField<'a> {
+    pub fn new(idx: usize, field: &'a Field) -> Result<Self> {
+        let Field {
+            ident, ty, attrs, ..
+        } = field;
+        let cfgs = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("cfg_attr"))
+            .cloned()
+            .collect();
+        let docs = attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect();
+        let attrs = FieldAttrs::from_attrs(attrs)?;
+        let (member, ident) = match (ident, &attrs.name) {
+            (Some(ident), None) => (Member::Named(ident.clone()), ident.clone()),
+            (None, Some(name)) => (Member::Unnamed(Index::from(idx)), name.clone()),
+            (None, None) => (Member::Unnamed(Index::from(idx)), format_ident!("_{idx}")),
+            (Some(_), Some(name)) => {
+                return Err(Error::new_spanned(name, "only positional fields can be named"));
+            }
+        };
+        let kind = if let Some(sub_builder) = &attrs.sub_builder {
+            if attrs.skip || attrs.each.is_some() || attrs.default.is_some() {
+                return Err(Error::new_spanned(
+                    sub_builder,
+                    "sub_builder can't be combined with `skip`, `each` or `default`",
+                ));
+            }
+            let builder = match &attrs.sub_builder_ty {
+                Some(path) => Type::Path(TypePath {
+                    qself: None,
+                    path: path.clone(),
+                }),
+                None => sub_builder_ty(ty).ok_or_else(|| {
+                    Error::new_spanned(ty, "sub_builder needs a path to a struct deriving `Builder`")
+                })?,
+            };
+            FieldKind::SubBuilder(builder)
+        } else if attrs.skip {
+            if let Some(each_method_name) = &attrs.each {
+                return Err(Error::new_spanned(
+                    each_method_name,
+                    "skipped fields have no setters",
+                ));
+            }
+            FieldKind::Skip
+        } else if let Some(each_method_name) = &attrs.each {
+            let (collection, optional) = match is_option(ty) {
+                Some(ty_inner) => (ty_inner, true),
+                None => (ty, false),
+            };
+            let item = if let Some((k, v)) = is_map(collection) {
+                EachItem::Pair(k, v)
+            } else if let Some(ty_inner) = is_collection(collection) {
+                EachItem::One(ty_inner)
+            } else {
+                EachItem::Item
+            };
+            FieldKind::Each {
+                method: each_method_name.clone(),
+                collection,
+                item,
+                optional,
+            }
+        } else if let Some((policy, _)) = &attrs.merge {
+            return Err(Error::new_spanned(policy, "merge policies only apply to `each` fields"));
+        } else if let Some(ty_inner) = is_option(ty) {
+            FieldKind::Option(ty_inner)
+        } else {
+            FieldKind::Plain
+        };
+        if let Some(env) = &attrs.env
+            && !matches!(kind, FieldKind::Plain | FieldKind::Option(_))
+        {
+            return Err(Error::new_spanned(env, "env only applies to plain and `Option` fields"));
+        }
+        if let Some((group, _)) = &attrs.group
+            && matches!(kind, FieldKind::Skip | FieldKind::SubBuilder(_))
+        {
+            // a skipped field is never set, a sub builder always is
+            return Err(Error::new_spanned(group, "group doesn't apply to `skip` or `sub_builder` fields"));
+        }
+        if let Some(start_fn) = &attrs.start_fn
+            && !(matches!(kind, FieldKind::Plain)
+                && attrs.default.is_none()
+                && attrs.env.is_none()
+                && attrs.group.is_none())
+        {
+            return Err(Error::new_spanned(
+                start_fn,
+                "start_fn only applies to required fields, without `Option`, `default`, `env` or `group`",
+            ));
+        }
+        Ok(BuilderField {
+            member,
+            ident,
+            ty,
+            attrs,
+            kind,
+            cfgs,
+            docs,
+        })
+    }
+
+    // the field name without `r#`, for the names and messages built from it
+    pub fn name(&self) -> Ident {
+        self.ident.unraw()
+    }
+
+    pub fn cfgs(&self) -> TokenStream {
+        let cfgs = &self.cfgs;
+        quote!(#(#cfgs)*)
+    }
+
+    // the typestate a required field starts in, already set when `#[cfg]` compiles
+    // its setter out so `build` stays reachable
+    pub fn initial_state(&self) -> TokenStream {
+        if self.attrs.start_fn.is_some() {
+            return quote!(true);
+        }
+        let predicates = self
+            .cfgs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .filter_map(|attr| attr.parse_args::<TokenStream>().ok())
+            .collect::<Vec<_>>();
+        if predicates.is_empty() {
+            quote!(false)
+        } else {
+            quote!({ !(#(::core::cfg!(#predicates))&&*) })
+        }
+    }
+
+    // `build` fails without it
+    pub fn is_required(&self) -> bool {
+        matches!(self.kind, FieldKind::Plain) && self.attrs.default.is_none()
+    }
+
+    // built from `Default::default()`, so `build` needs `T: Default`
+    pub fn uses_default_trait(&self) -> bool {
+        match self.attrs.default {
+            Some(FieldDefault::Trait) => true,
+            Some(FieldDefault::Expr(_)) => false,
+            None => matches!(self.kind, FieldKind::Skip),
+        }
+    }
+
+    // `setter(name = "with_exec")`, or the field name after `setter(prefix = "with_")`
+    pub fn setter_name(&self, setter: &SetterAttrs) -> Ident {
+        match (&setter.name, &setter.prefix) {
+            (Some(name), _) => name.clone(),
+            (None, Some(prefix)) => format_ident!("{prefix}{}", self.name(), span = self.ident.span()),
+            (None, None) => self.ident.clone(),
+        }
+    }
+
+    // what one call of the `each` setter adds
+    pub fn each_item_ty(&self) -> Option<TokenStream> {
+        match self.kind {
+            FieldKind::Each { collection, ref item, .. } => Some(match item {
+                EachItem::One(ty) => quote!(#ty),
+                EachItem::Pair(k, v) => quote!((#k, #v)),
+                EachItem::Item => quote!(<#collection as ::core::iter::IntoIterator>::Item),
+            }),
+            _ => None,
+        }
+    }
+
+    // what the peek getter borrows from a set slot
+    pub fn peek_ty(&self) -> Option<TokenStream> {
+        let ty = self.ty;
+        match self.kind {
+            FieldKind::Each { collection, .. } => Some(quote!(#collection)),
+            FieldKind::Option(ty_inner) => Some(quote!(#ty_inner)),
+            FieldKind::Plain => Some(quote!(#ty)),
+            FieldKind::Skip | FieldKind::SubBuilder(_) => None,
+        }
+    }
+
+    // type of the slot in the builder, skipped fields have none
+    pub fn builder_ty(&self) -> Option<TokenStream> {
+        let ty = self.ty;
+        match self.kind {
+            FieldKind::Each { collection, .. } => Some(quote!(::core::option::Option<#collection>)),
+            FieldKind::Option(ty_inner) => Some(quote!(::core::option::Option<#ty_inner>)),
+            FieldKind::Plain => Some(quote!(::core::option::Option<#ty>)),
+            FieldKind::Skip => None,
+            FieldKind::SubBuilder(ref builder) => Some(quote!(#builder)),
+        }
+    }
+
+    // the slot for `From<T>`, from the value of the field bound to its ident
+    pub fn slot_value(&self) -> Option<TokenStream> {
+        let ident = &self.ident;
+        match self.kind {
+            FieldKind::Option(_) | FieldKind::Each { optional: true, .. } => Some(quote!(#ident)),
+            FieldKind::Each { .. } | FieldKind::Plain => Some(quote!(::core::option::Option::Some(#ident))),
+            FieldKind::SubBuilder(_) => Some(quote!(::core::convert::From::from(#ident))),
+            FieldKind::Skip => None,
+        }
+    }
+
+    pub fn merge_policy(&self) -> MergePolicy {
+        self.attrs.merge.as_ref().map(|(_, policy)| *policy).unwrap_or_default()
+    }
+
+    // `merge`: a slot set in `other` overrides this one, `each` fields append by default
+    pub fn merge_step(&self) -> Option<TokenStream> {
+        let ident = &self.ident;
+        let cfgs = self.cfgs();
+        let step = match self.kind {
+            FieldKind::Each { .. } if self.merge_policy() == MergePolicy::Append => Some(quote! {
+                if let ::core::option::Option::Some(v) = other.#ident {
+                    ::core::iter::Extend::extend(
+                        self.#ident.get_or_insert_with(::core::default::Default::default),
+                        v,
+                    );
+                }
+            }),
+            FieldKind::Each { .. } | FieldKind::Option(_) | FieldKind::Plain => Some(quote! {
+                if other.#ident.is_some() {
+                    self.#ident = other.#ident;
+                }
+            }),
+            FieldKind::SubBuilder(_) => {
+                let needs = self.sub_builder_needs();
+                Some(quote! {{
+                    #needs
+                    self.#ident.__merge(other.#ident);
+                }})
+            }
+            FieldKind::Skip => None,
+        };
+        step.map(|step| quote!(#cfgs #step))
+    }
+
+    // the value `build` puts into the struct, required fields are checked before
+    pub fn build_value(&self, pattern: Pattern) -> TokenStream {
+        // `#[builder(default)]` is only evaluated when the setter was never called
+        let default = self.attrs.default.as_ref().map(FieldDefault::to_expr);
+        match self.kind {
+            FieldKind::Skip => return default.unwrap_or_else(|| FieldDefault::Trait.to_expr()),
+            FieldKind::SubBuilder(_) => return self.sub_built().to_token_stream(),
+            _ => {}
+        }
+        let mut take = pattern.take_field(&self.ident);
+        if self.attrs.env.is_some() {
+            let env = self.env_value();
+            take = quote!(#take.or(#env));
+        }
+        let optional = matches!(
+            self.kind,
+            FieldKind::Option(_) | FieldKind::Each { optional: true, .. }
+        );
+        match (&self.kind, default) {
+            (_, None) if optional => take,
+            (FieldKind::Each { .. }, None) => quote!(#take.unwrap_or_default()),
+            (_, None) => quote!(#take.unwrap()),
+            (_, Some(default)) if optional => quote! {
+                match #take {
+                    ::core::option::Option::Some(v) => ::core::option::Option::Some(v),
+                    ::core::option::Option::None => #default,
+                }
+            },
+            (_, Some(default)) => quote! {
+                match #take {
+                    ::core::option::Option::Some(v) => v,
+                    ::core::option::Option::None => #default,
+                }
+            },
+        }
+    }
+
+    // the value parsed from `#[builder(env = "..")]`, `None` when the setter was called
+    pub fn env_value(&self) -> Ident {
+        format_ident!("__env_{}", self.name())
+    }
+
+    // what `env` parses, `T` of `Option<T>` for optional fields
+    pub fn env_ty(&self) -> Option<&Type> {
+        self.attrs.env.as_ref()?;
+        match self.kind {
+            FieldKind::Option(ty_inner) => Some(ty_inner),
+            _ => Some(self.ty),
+        }
+    }
+
+    // `let __env_x = ..;` before anything is taken, a parse failure leaves the builder untouched,
+    // the missing check of a required field looks at the parsed value, not the variable again
+    pub fn env_step(&self, error: &Ident) -> Option<TokenStream> {
+        let env = self.attrs.env.as_ref()?;
+        let ty = self.env_ty()?;
+        let ident = &self.ident;
+        let value = self.env_value();
+        let cfgs = self.cfgs();
+        Some(quote! {
+            #cfgs
+            let #value: ::core::option::Option<#ty> = if self.#ident.is_none() {
+                match ::std::env::var(#env) {
+                    ::core::result::Result::Ok(v) => match <#ty as ::core::str::FromStr>::from_str(&v) {
+                        ::core::result::Result::Ok(v) => ::core::option::Option::Some(v),
+                        ::core::result::Result::Err(err) => {
+                            return ::core::result::Result::Err(#error::Env {
+                                var: #env,
+                                message: ::std::string::ToString::to_string(&err),
+                            });
+                        }
+                    },
+                    ::core::result::Result::Err(::std::env::VarError::NotPresent) => ::core::option::Option::None,
+                    ::core::result::Result::Err(err) => {
+                        return ::core::result::Result::Err(#error::Env {
+                            var: #env,
+                            message: ::std::string::ToString::to_string(&err),
+                        });
+                    }
+                }
+            } else {
+                ::core::option::Option::None
+            };
+        })
+    }
+
+    // `s.field("x", ..);` for `#[builder(debug)]`
+    pub fn debug_field(&self) -> Option<TokenStream> {
+        let ident = &self.ident;
+        let name = self.name().to_string();
+        let cfgs = self.cfgs();
+        let value = match self.kind {
+            FieldKind::Skip => return None,
+            // before the sub builder, which would print every field of its own
+            _ if self.attrs.redact => quote!(&::core::format_args!("<redacted>")),
+            FieldKind::SubBuilder(_) => quote!(&self.#ident),
+            FieldKind::Each {
+                item: EachItem::One(_) | EachItem::Pair(..),
+                ..
+            } => quote!(&::core::format_args!("{:?} ({} items)", v, v.len())),
+            _ => quote!(v),
+        };
+        Some(match self.kind {
+            FieldKind::SubBuilder(_) => quote! {
+                #cfgs
+                s.field(#name, #value);
+            },
+            _ => quote! {
+                #cfgs
+                match &self.#ident {
+                    ::core::option::Option::Some(v) => s.field(#name, #value),
+                    ::core::option::Option::None => s.field(#name, &::core::format_args!("<unset>")),
+                };
+            },
+        })
+    }
+
+    // the value of a sub builder, built before anything is taken from the outer builder
+    fn sub_built(&self) -> Ident {
+        format_ident!("__sub_{}", self.name())
+    }
+
+    // an `owned` inner builder can't be updated through `&mut`, nor built from behind one,
+    // so require the marker trait of its pattern instead of failing somewhere in the generated
+    // code, `__TlsBuilderMutableOrImmutable` next to `TlsBuilder`
+    pub fn sub_builder_needs_fn(&self, pattern: Pattern) -> Option<TokenStream> {
+        let FieldKind::SubBuilder(Type::Path(TypePath { ref path, .. })) = self.kind else {
+            return None;
+        };
+        let span = self.sub_builder_span();
+        let mut needs = path.clone();
+        let last = needs.segments.last_mut().unwrap();
+        last.ident = sub_builder_trait(&last.ident, pattern);
+        last.ident.set_span(span);
+        last.arguments = PathArguments::None;
+        let name = self.sub_builder_needs_name();
+        let cfgs = self.cfgs();
+        // one function for every place using the sub builder, so the error is reported once
+        Some(quote_spanned! {span=>
+            #cfgs
+            fn #name<T: ?::core::marker::Sized + #needs>() {}
+        })
+    }
+
+    fn sub_builder_needs(&self) -> TokenStream {
+        let FieldKind::SubBuilder(ref builder) = self.kind else {
+            return quote!();
+        };
+        let span = self.sub_builder_span();
+        let name = self.sub_builder_needs_name();
+        // the inner builder is named after the field type, point at `sub_builder` instead
+        let builder = builder
+            .to_token_stream()
+            .into_iter()
+            .map(|mut token| {
+                token.set_span(span);
+                token
+            })
+            .collect::<TokenStream>();
+        quote_spanned!(span=> Self::#name::<#builder>();)
+    }
+
+    fn sub_builder_needs_name(&self) -> Ident {
+        format_ident!("__sub_builder_needs_{}", self.name())
+    }
+
+    fn sub_builder_span(&self) -> Span {
+        self.attrs.sub_builder.as_ref().map_or_else(Span::call_site, Ident::span)
+    }
+
+    // the checks of the sub builder, its error under the path of the field
+    pub fn sub_check(&self, error: &Ident) -> Option<TokenStream> {
+        let FieldKind::SubBuilder(_) = self.kind else {
+            return None;
+        };
+        let ident = &self.ident;
+        let name = self.name();
+        let cfgs = self.cfgs();
+        Some(quote! {
+            #cfgs
+            if let ::core::result::Result::Err(err) = self.#ident.__check_build() {
+                let (path, message) = err.__into_sub_builder_error(stringify!(#name));
+                return ::core::result::Result::Err(#error::SubBuilder { path, message });
+            }
+        })
+    }
+
+    // `let __sub_x = ..;` returning the error of the sub builder under the path of the field
+    pub fn sub_build(&self, pattern: Pattern, error: &Ident) -> Option<TokenStream> {
+        let FieldKind::SubBuilder(_) = self.kind else {
+            return None;
+        };
+        let ident = &self.ident;
+        let name = self.name();
+        let built = self.sub_built();
+        let needs = self.sub_builder_needs();
+        // an owned inner builder has to be moved out to be built
+        let inner = match pattern {
+            Pattern::Mutable | Pattern::Immutable => quote!(self.#ident.build()),
+            Pattern::Owned => quote! {{
+                #[allow(unused_mut)]
+                let mut inner = self.#ident;
+                inner.build()
+            }},
+        };
+        let cfgs = self.cfgs();
+        Some(quote! {
+            #cfgs
+            #needs
+            #cfgs
+            let #built = match #inner {
+                ::core::result::Result::Ok(v) => v,
+                ::core::result::Result::Err(err) => {
+                    let (path, message) = err.__into_sub_builder_error(stringify!(#name));
+                    return ::core::result::Result::Err(#error::SubBuilder { path, message });
+                }
+            };
+        })
+    }
+}
+
+// `server::Server<T>` is built by `server::ServerBuilder<T>`
+fn sub_builder_ty(ty: &Type) -> Option<Type> {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+        let mut path = path.clone();
+        let last = path.segments.last_mut()?;
+        last.ident = format_ident!("{}Builder", last.ident);
+        Some(Type::Path(TypePath { qself: None, path }))
+    } else {
+        None
+    }
+}
+
+// the marker trait an inner builder implements when an outer builder with `pattern` can use it
+pub fn sub_builder_trait(builder: &Ident, pattern: Pattern) -> Ident {
+    match pattern {
+        Pattern::Immutable => format_ident!("__{builder}Immutable"),
+        Pattern::Mutable | Pattern::Owned => format_ident!("__{builder}MutableOrImmutable"),
+    }
+}
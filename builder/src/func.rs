@@ -0,0 +1,420 @@
+use proc_macro2::*;
+use quote::*;
+use syn::ext::IdentExt;
+use syn::*;
+
+use crate::attr::*;
+use crate::field::*;
+
+// `#[builder] fn connect(..)`, or `#[builder] impl Client { #[builder] fn connect(&self, ..) }`
+pub fn expand(args: TokenStream, item: Item) -> Result<TokenStream> {
+    match item {
+        Item::Fn(ItemFn {
+            attrs,
+            vis,
+            sig,
+            block,
+        }) => {
+            let expanded = BuilderFn::new(args, &attrs, &vis, &sig, None)?;
+            let BuilderFn {
+                items,
+                start,
+                inner,
+            } = expanded;
+            Ok(quote! {
+                #start
+                #inner #block
+                #items
+            })
+        }
+        Item::Impl(mut item_impl) => {
+            if !args.is_empty() {
+                return Err(Error::new_spanned(
+                    args,
+                    "put the options on the `#[builder]` methods",
+                ));
+            }
+            if let Some((_, path, _)) = &item_impl.trait_ {
+                return Err(Error::new_spanned(
+                    path,
+                    "builder methods need an inherent impl",
+                ));
+            }
+            let mut items = vec![];
+            let mut impl_items = vec![];
+            for impl_item in std::mem::take(&mut item_impl.items) {
+                let ImplItem::Fn(mut method) = impl_item else {
+                    impl_items.push(impl_item);
+                    continue;
+                };
+                let Some(idx) = method
+                    .attrs
+                    .iter()
+                    .position(|attr| attr.path().is_ident("builder"))
+                else {
+                    impl_items.push(ImplItem::Fn(method));
+                    continue;
+                };
+                let args = match method.attrs.remove(idx).meta {
+                    Meta::Path(_) => TokenStream::new(),
+                    Meta::List(list) => list.tokens,
+                    meta @ Meta::NameValue(_) => {
+                        return Err(Error::new_spanned(meta, "expected `#[builder(..)]`"))
+                    }
+                };
+                let expanded = BuilderFn::new(
+                    args,
+                    &method.attrs,
+                    &method.vis,
+                    &method.sig,
+                    Some(&item_impl),
+                )?;
+                let BuilderFn {
+                    items: fn_items,
+                    start,
+                    inner,
+                } = expanded;
+                let block = &method.block;
+                impl_items.push(parse_quote!(#start));
+                impl_items.push(parse_quote!(#inner #block));
+                items.push(fn_items);
+            }
+            item_impl.items = impl_items;
+            Ok(quote! {
+                #item_impl
+                #(#items)*
+            })
+        }
+        _ => Err(Error::new_spanned(
+            item,
+            "expected a function or an impl block",
+        )),
+    }
+}
+
+struct BuilderFn {
+    // the arguments struct, its builder and `call`, next to the function
+    items: TokenStream,
+    // `fn connect() -> ConnectBuilder` in place of the function
+    start: TokenStream,
+    // the original function as `fn __connect(..)`, without its body
+    inner: TokenStream,
+}
+
+impl BuilderFn {
+    fn new(
+        args: TokenStream,
+        attrs: &[Attribute],
+        vis: &Visibility,
+        sig: &Signature,
+        owner: Option<&ItemImpl>,
+    ) -> Result<Self> {
+        let fn_name = &sig.ident;
+        let self_ty = owner.map(|item_impl| item_impl.self_ty.as_ref());
+        let owner_name = match self_ty {
+            Some(Type::Path(TypePath { qself: None, path })) => {
+                path.segments.last().unwrap().ident.to_string()
+            }
+            Some(ty) => return Err(Error::new_spanned(ty, "builder methods need a named type")),
+            None => String::new(),
+        };
+        let camel = to_camel_case(&fn_name.unraw().to_string());
+        let builder_name = format_ident!("{owner_name}{camel}Builder", span = fn_name.span());
+        let args_name = format_ident!("__{owner_name}{camel}Args", span = fn_name.span());
+        let inner_name = format_ident!("__{}", fn_name.unraw(), span = fn_name.span());
+
+        // the arguments struct takes the generics of the impl and of the function
+        let mut params = vec![];
+        let mut predicates = vec![];
+        if let Some(item_impl) = owner {
+            params.extend(item_impl.generics.params.iter().cloned());
+            predicates.extend(
+                item_impl
+                    .generics
+                    .where_clause
+                    .iter()
+                    .flat_map(|w| w.predicates.iter().cloned()),
+            );
+        }
+        params.extend(sig.generics.params.iter().cloned());
+        predicates.extend(
+            sig.generics
+                .where_clause
+                .iter()
+                .flat_map(|w| w.predicates.iter().cloned()),
+        );
+
+        let mut fields: Vec<Field> = vec![];
+        let mut call_args = vec![];
+        let mut elided_self = false;
+        let mut inner_sig = sig.clone();
+        inner_sig.ident = inner_name.clone();
+        for input in inner_sig.inputs.iter_mut() {
+            match input {
+                FnArg::Receiver(receiver) => {
+                    let self_ty = self_ty.unwrap();
+                    let ty: Type = match &receiver.reference {
+                        Some((_, lifetime)) => {
+                            let lifetime = lifetime.clone().unwrap_or_else(|| {
+                                elided_self = true;
+                                parse_quote!('__self)
+                            });
+                            let mutability = &receiver.mutability;
+                            parse_quote!(&#lifetime #mutability #self_ty)
+                        }
+                        None => replace_self(&receiver.ty, self_ty)?,
+                    };
+                    fields.push(parse_quote! {
+                        #[builder(start_fn)]
+                        __self: #ty
+                    });
+                }
+                FnArg::Typed(PatType { attrs, pat, ty, .. }) => {
+                    let Pat::Ident(PatIdent {
+                        ident,
+                        by_ref: None,
+                        subpat: None,
+                        ..
+                    }) = pat.as_ref()
+                    else {
+                        return Err(Error::new_spanned(
+                            pat,
+                            "builder arguments need a plain name",
+                        ));
+                    };
+                    let ty = match self_ty {
+                        Some(self_ty) => replace_self(ty, self_ty)?,
+                        None => ty.as_ref().clone(),
+                    };
+                    // `#[builder(..)]` moves to the field, the function keeps the rest
+                    let (builder_attrs, other_attrs) = std::mem::take(attrs)
+                        .into_iter()
+                        .partition::<Vec<_>, _>(|attr| attr.path().is_ident("builder"));
+                    *attrs = other_attrs;
+                    // `#[cfg]` also goes on the field and everywhere the argument is passed on
+                    let cfgs = attrs
+                        .iter()
+                        .filter(|attr| attr.path().is_ident("cfg"))
+                        .collect::<Vec<_>>();
+                    // parameters can't have doc comments, the setters get one naming them
+                    let doc = format!("the `{}` argument", ident.unraw());
+                    fields.push(parse_quote! {
+                        #(#cfgs)*
+                        #[doc = #doc]
+                        #(#builder_attrs)*
+                        #ident: #ty
+                    });
+                    call_args.push(quote!(#(#cfgs)* #ident));
+                }
+            }
+        }
+        if elided_self {
+            params.insert(0, parse_quote!('__self));
+        }
+        // lifetimes come first
+        params.sort_by_key(|gp| !matches!(gp, GenericParam::Lifetime(_)));
+        // a parameter only the return type uses still has to appear in the struct
+        let phantom_params = params
+            .iter()
+            .filter_map(|gp| match gp {
+                GenericParam::Lifetime(LifetimeParam { lifetime, .. }) => {
+                    Some(quote!(&#lifetime ()))
+                }
+                GenericParam::Type(TypeParam { ident, .. }) => Some(quote!(#ident)),
+                GenericParam::Const(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if !phantom_params.is_empty() {
+            fields.push(parse_quote! {
+                #[builder(skip)]
+                __phantom: ::core::marker::PhantomData<fn() -> (#(#phantom_params,)*)>
+            });
+        }
+
+        let struct_attrs: Attribute =
+            parse_quote!(#[builder(typestate, name = #builder_name, #args)]);
+        let derive_input: DeriveInput = parse_quote! {
+            #struct_attrs
+            #[doc(hidden)]
+            #vis struct #args_name<#(#params),*> where #(#predicates),* {
+                #(#fields),*
+            }
+        };
+        let StructAttrs { build_fn, .. } = StructAttrs::from_attrs(&derive_input.attrs)?;
+        let fallible = build_fn.validate.is_some() || build_fn.validate_built.is_some();
+        // the typestate of each required field, and the `start_fn` ones `builder(..)` takes
+        let mut start_states = vec![];
+        let mut start_params = vec![];
+        for (idx, field) in fields.iter().enumerate() {
+            let field = BuilderField::new(idx, field)?;
+            if field.is_required() {
+                start_states.push(field.initial_state());
+            }
+            if field.attrs.start_fn.is_some() {
+                start_params.push((field.ident, field.ty.clone()));
+            }
+        }
+        let required = start_states.len();
+
+        // `__ConnectArgs` without the `#[builder]` attributes the derive consumed
+        let mut args_struct = derive_input.clone();
+        args_struct
+            .attrs
+            .retain(|attr| !attr.path().is_ident("builder"));
+        if let Data::Struct(DataStruct { fields, .. }) = &mut args_struct.data {
+            for field in fields.iter_mut() {
+                field.attrs.retain(|attr| !attr.path().is_ident("builder"));
+            }
+        }
+        let derived = crate::expand(&derive_input);
+
+        let param_args = params
+            .iter()
+            .map(|gp| match gp {
+                GenericParam::Lifetime(LifetimeParam { lifetime, .. }) => {
+                    lifetime.to_token_stream()
+                }
+                GenericParam::Type(TypeParam { ident, .. })
+                | GenericParam::Const(ConstParam { ident, .. }) => ident.to_token_stream(),
+            })
+            .collect::<Vec<_>>();
+        let (impl_generics, _, where_clause) = derive_input.generics.split_for_impl();
+        let receiver = sig.receiver();
+
+        // a method starts with `self` already set, as a `start_fn` field
+        let start_args = param_args.iter().map(|arg| {
+            if elided_self && arg.to_string() == "'__self" {
+                quote!('_)
+            } else {
+                arg.clone()
+            }
+        });
+        let start_fn = {
+            let fn_generics = &sig.generics;
+            let fn_where = &sig.generics.where_clause;
+            let docs = attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+            let start_idents = start_params.iter().map(|(ident, _)| {
+                if ident == "__self" {
+                    quote!(self)
+                } else {
+                    quote!(#ident)
+                }
+            });
+            let start_inputs = receiver.map(|receiver| quote!(#receiver)).into_iter().chain(
+                start_params
+                    .iter()
+                    .filter(|(ident, _)| ident != "__self")
+                    .map(|(ident, ty)| quote!(#ident: #ty)),
+            );
+            quote! {
+                #(#docs)*
+                #vis fn #fn_name #fn_generics (#(#start_inputs),*) -> #builder_name<#(#start_args,)* #(#start_states),*>
+                #fn_where
+                {
+                    #args_name::builder(#(#start_idents),*)
+                }
+            }
+        };
+
+        let finals = (0..required).map(|_| quote!(true));
+        let mut bindings = call_args.clone();
+        if receiver.is_some() {
+            bindings.insert(0, quote!(__self));
+        }
+        let turbofish = sig
+            .generics
+            .params
+            .iter()
+            .filter_map(|gp| match gp {
+                GenericParam::Lifetime(_) => None,
+                GenericParam::Type(TypeParam { ident, .. })
+                | GenericParam::Const(ConstParam { ident, .. }) => Some(ident),
+            })
+            .collect::<Vec<_>>();
+        let turbofish = if turbofish.is_empty() {
+            quote!()
+        } else {
+            quote!(::<#(#turbofish),*>)
+        };
+        let callee = match (self_ty, receiver) {
+            (Some(_), Some(_)) => quote!(__self.#inner_name),
+            (Some(self_ty), None) => quote!(<#self_ty>::#inner_name),
+            (None, _) => quote!(#inner_name),
+        };
+        let await_call = sig.asyncness.map(|_| quote!(.await));
+        let mut call = quote!(#callee #turbofish (#(#call_args),*) #await_call);
+        let mut output = match &sig.output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ret) => match self_ty {
+                Some(self_ty) => replace_self(ret, self_ty)?.into_token_stream(),
+                None => ret.to_token_stream(),
+            },
+        };
+        let mut build = quote!(self.build());
+        if fallible {
+            // the error of `build_fn(validate)` comes back from `call`
+            let error_name = format_ident!("{builder_name}Error");
+            output = quote!(::core::result::Result<#output, #error_name>);
+            call = quote!(::core::result::Result::Ok(#call));
+            build = quote!(#build?);
+        }
+        let asyncness = &sig.asyncness;
+        let unsafety = &sig.unsafety;
+        let call_doc = format!("calls `{}` with the arguments set", fn_name.unraw());
+        let call_fn = quote! {
+            impl #impl_generics #builder_name<#(#param_args,)* #(#finals),*> #where_clause {
+                #[doc = #call_doc]
+                #vis #asyncness #unsafety fn call(self) -> #output {
+                    let #args_name { #(#bindings,)* .. } = #build;
+                    #call
+                }
+            }
+        };
+
+        Ok(BuilderFn {
+            items: quote! {
+                #args_struct
+                #derived
+                #call_fn
+            },
+            start: start_fn,
+            inner: quote! {
+                #(#attrs)*
+                #inner_sig
+            },
+        })
+    }
+}
+
+// `connect_all` to `ConnectAll`
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// `Self` means nothing outside the impl, the arguments struct spells out the type
+fn replace_self(ty: &Type, self_ty: &Type) -> Result<Type> {
+    fn replace(tokens: TokenStream, self_ty: &Type) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|tt| match tt {
+                TokenTree::Ident(ident) if ident == "Self" => self_ty.to_token_stream(),
+                TokenTree::Group(group) => {
+                    let mut replaced =
+                        Group::new(group.delimiter(), replace(group.stream(), self_ty));
+                    replaced.set_span(group.span());
+                    replaced.into_token_stream()
+                }
+                tt => tt.into_token_stream(),
+            })
+            .collect()
+    }
+    parse2(replace(ty.to_token_stream(), self_ty))
+}
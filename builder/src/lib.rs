@@ -1,123 +1,1162 @@
 #![feature(let_chains)]
 
+use std::collections::HashSet;
+
 use mylib_macro::*;
 use proc_macro2::*;
 use quote::*;
+use syn::ext::IdentExt;
 use syn::*;
 
+mod attr;
+mod field;
+mod func;
+use attr::*;
+use field::*;
+
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    proc_macro::TokenStream::from(expand(&input))
+}
+
+// `#[builder]` on a function, or on an `impl` block with `#[builder]` methods
+#[proc_macro_attribute]
+pub fn builder(args: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as Item);
+    let expanded = func::expand(TokenStream::from(args), item).unwrap_or_else(Error::into_compile_error);
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn expand(input: &DeriveInput) -> TokenStream {
+    match input.data {
+        Data::Struct(DataStruct { ref fields, .. }) => expand_fields(input, None, fields),
+        Data::Enum(DataEnum { ref variants, .. }) => {
+            // one builder per variant with fields, named in the variant's own `#[builder(name)]`
+            if let Err(err) = StructAttrs::from_attrs(&input.attrs).and_then(|attrs| match attrs.name {
+                Some(name) => Err(Error::new_spanned(name, "name the builder on each variant")),
+                None => Ok(()),
+            }) {
+                return err.to_compile_error();
+            }
+            variants
+                .iter()
+                .filter(|variant| !matches!(variant.fields, Fields::Unit))
+                .map(|variant| expand_fields(input, Some(variant), &variant.fields))
+                .collect()
+        }
+        Data::Union(_) => Error::new_spanned(input, "should be struct or enum").to_compile_error(),
+    }
+}
 
-    let vis = &input.vis;
+// the builder of a struct, or of one variant of an enum
+fn expand_fields(input: &DeriveInput, variant: Option<&Variant>, data_fields: &Fields) -> TokenStream {
     let struct_name = &input.ident;
-    let struct_builder_name = format_ident!("{struct_name}Builder");
+    // `Shape::Circle { .. }` is built by `Shape::circle_builder()` as `ShapeCircleBuilder`
+    let (constructor, builder_fn, default_builder_name) = match variant {
+        Some(Variant { ident, .. }) => (
+            quote!(#struct_name::#ident),
+            format_ident!("{}_builder", to_snake_case(&ident.unraw().to_string()), span = ident.span()),
+            format_ident!("{struct_name}{}Builder", ident.unraw()),
+        ),
+        None => (
+            quote!(#struct_name),
+            format_ident!("builder"),
+            format_ident!("{struct_name}Builder"),
+        ),
+    };
+    // what the docs of the generated items name, `Shape::Circle` for a variant
+    let target = match variant {
+        Some(Variant { ident, .. }) => format!("{struct_name}::{}", ident.unraw()),
+        None => struct_name.to_string(),
+    };
+    // a variant may set again what the enum already did
+    let attrs = match variant {
+        Some(variant) => vec![&input.attrs[..], &variant.attrs[..]],
+        None => vec![&input.attrs[..]],
+    };
 
     let mut errors = vec![];
     let mut fields_builder = vec![];
     let mut methods_builder = vec![];
     let mut build_internal = vec![];
+    // `try_build` of an owned builder builds in place like `mutable`, to hand the builder back
+    let mut build_internal_in_place = vec![];
+    let mut build_checks = vec![];
+    // the same for `build`, which has read the variables of `env` fields already
+    let mut build_checks_env_read = vec![];
+    let mut from_members = vec![];
+    let mut from_slots = vec![];
+    let mut merge_steps = vec![];
+    let mut merge_bounds = vec![];
+    // peek getters, only kept where no setter has the same name
+    let mut peek_getters = vec![];
+    let mut groups: Vec<FieldGroup> = vec![];
+    // every generated method, a name generated twice is an error
+    let mut methods_names = vec![];
 
-    let attr_id_builder = format_ident!("builder");
+    // bounds `build` needs beyond the struct's own, inferred like `derive(CustomDebug)`
+    let mut path_with_params = HashSet::new();
+    let mut build_bounds = vec![];
+    let gpids = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|gp| {
+            if let GenericParam::Type(TypeParam { ident, .. }) = gp {
+                Some(ident)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
 
-    if let Data::Struct(DataStruct {
-        fields: Fields::Named(FieldsNamed { ref named, .. }),
-        ..
-    }) = input.data
-    {
-        for Field {
-            ident, ty, attrs, ..
-        } in named
-        {
-            // find `#[builder(..)]`
-            if let Some((attr, tokens)) = attrs.iter().find_map(|attr| {
-                if let Meta::List(MetaList {
-                        path,
-                        tokens,
-                        ..
-                    }) = &attr.meta
-                    && path.is_ident(&attr_id_builder)
-                {
-                    return Some((attr, tokens));
+    let struct_attrs = StructAttrs::from_layers(&attrs).unwrap_or_else(|err| {
+        errors.push(err);
+        StructAttrs::default()
+    });
+    let vis = struct_attrs.vis.as_ref().unwrap_or(&input.vis);
+    let struct_builder_name = match struct_attrs.name {
+        Some(ref name) => name.clone(),
+        None => default_builder_name,
+    };
+    let struct_builder_error_name = format_ident!("{struct_builder_name}Error");
+    let derives = &struct_attrs.derives;
+    let pattern = struct_attrs.pattern;
+    let typestate = struct_attrs.typestate.is_some();
+    let root = struct_attrs.crate_root.path();
+    let setter_receiver = pattern.setter_receiver();
+    let setter_output = pattern.setter_output();
+    let build_receiver = pattern.build_receiver();
+
+    let mut fields = vec![];
+    for (idx, field) in data_fields.iter().enumerate() {
+        match BuilderField::new(idx, field) {
+            Ok(field) => fields.push(field),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    // typestate: required field `n` is set when `const __S{n}: bool` is `true`
+    let states = if typestate {
+        fields
+            .iter()
+            .filter(|field| field.is_required())
+            .enumerate()
+            .map(|(n, _)| format_ident!("__S{n}"))
+            .collect()
+    } else {
+        vec![]
+    };
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut builder_generics = generics.clone();
+    builder_generics
+        .params
+        .extend(states.iter().map(|s| -> GenericParam { parse_quote!(const #s: bool = false) }));
+    let (builder_impl_generics, builder_ty_generics, _) = builder_generics.split_for_impl();
+    // the builder type with every state fixed
+    let user_args = generics
+        .params
+        .iter()
+        .map(|gp| match gp {
+            GenericParam::Lifetime(LifetimeParam { lifetime, .. }) => lifetime.to_token_stream(),
+            GenericParam::Type(TypeParam { ident, .. })
+            | GenericParam::Const(ConstParam { ident, .. }) => ident.to_token_stream(),
+        })
+        .collect::<Vec<_>>();
+    let builder_ty_with = |state_args: Vec<TokenStream>| {
+        if user_args.is_empty() && state_args.is_empty() {
+            quote!(#struct_builder_name)
+        } else {
+            quote!(#struct_builder_name<#(#user_args,)* #(#state_args),*>)
+        }
+    };
+    // `start_fn` fields are set by `builder(..)` already
+    let builder_initial = builder_ty_with(
+        fields
+            .iter()
+            .filter(|field| typestate && field.is_required())
+            .map(BuilderField::initial_state)
+            .collect(),
+    );
+    let builder_final = builder_ty_with(states.iter().map(|_| quote!(true)).collect());
+
+    // every slot of the builder, skipped fields have none
+    let mut fields_ident = fields
+        .iter()
+        .filter(|field| field.builder_ty().is_some())
+        .map(|field| field.ident.clone())
+        .collect::<Vec<_>>();
+    let mut fields_cfgs = fields
+        .iter()
+        .filter(|field| field.builder_ty().is_some())
+        .map(BuilderField::cfgs)
+        .collect::<Vec<_>>();
+    // what `builder(..)` puts in each slot
+    let mut fields_init = fields
+        .iter()
+        .filter(|field| field.builder_ty().is_some())
+        .map(|field| match field.attrs.start_fn {
+            Some(_) => {
+                let ident = &field.ident;
+                quote!(::core::option::Option::Some(#ident))
+            }
+            None => quote!(::core::default::Default::default()),
+        })
+        .collect::<Vec<_>>();
+    let start_params = fields
+        .iter()
+        .filter(|field| field.attrs.start_fn.is_some())
+        .map(|field| {
+            let BuilderField { ident, ty, .. } = field;
+            let cfgs = field.cfgs();
+            quote!(#cfgs #ident: #ty)
+        })
+        .collect::<Vec<_>>();
+    let skipped_tys = fields
+        .iter()
+        .filter(|field| matches!(field.kind, FieldKind::Skip))
+        .map(|field| field.ty)
+        .collect::<Vec<_>>();
+    // a variant may not use every parameter of the enum
+    let unused_params = match variant {
+        Some(_) => {
+            let used = fields
+                .iter()
+                .filter(|field| field.builder_ty().is_some())
+                .map(|field| field.ty.to_token_stream())
+                .collect::<TokenStream>();
+            generics
+                .params
+                .iter()
+                .filter_map(|gp| match gp {
+                    GenericParam::Type(TypeParam { ident, .. }) if !mentions(&used, ident) => Some(quote!(#ident)),
+                    GenericParam::Lifetime(LifetimeParam { lifetime, .. }) if !mentions(&used, &lifetime.ident) => {
+                        Some(quote!(&#lifetime ()))
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+        None => vec![],
+    };
+    if (!skipped_tys.is_empty() && !generics.params.is_empty()) || !unused_params.is_empty() {
+        // a parameter only used by skipped fields, or by no field of the variant, would be unused
+        let phantom = format_ident!("__phantom");
+        fields_builder.push(quote! {
+            #phantom: ::core::marker::PhantomData<fn() -> (#(#skipped_tys,)* #(#unused_params,)*)>
+        });
+        from_slots.push(quote!(#phantom: ::core::marker::PhantomData));
+        fields_ident.push(phantom);
+        fields_cfgs.push(quote!());
+        fields_init.push(quote!(::core::default::Default::default()));
+    }
+
+    let mut state_idx = 0;
+    for field in &fields {
+        let BuilderField {
+            member, ident, ty, ..
+        } = field;
+        let field_name = field.name();
+        if field.uses_default_trait() && used_generic_param(ty, gpids.as_slice(), &mut HashSet::new()) {
+            build_bounds.push(quote!(#ty: ::core::default::Default));
+        }
+        let cfgs = field.cfgs();
+        let docs = &field.docs;
+        let cfg_docs = quote!(#cfgs #(#docs)*);
+        let value = field.build_value(pattern);
+        build_internal.push(quote! {
+            #cfgs #member: #value
+        });
+        let value = field.build_value(Pattern::Mutable);
+        build_internal_in_place.push(quote! {
+            #cfgs #member: #value
+        });
+        let Some(builder_ty) = field.builder_ty() else {
+            continue;
+        };
+        let slot_value = field.slot_value();
+        from_members.push(match member {
+            Member::Named(_) => quote!(#cfgs #ident),
+            Member::Unnamed(_) => quote!(#cfgs #member: #ident),
+        });
+        from_slots.push(quote!(#cfgs #ident: #slot_value));
+        if pattern == Pattern::Immutable && !matches!(field.kind, FieldKind::SubBuilder(_)) {
+            // `build(&self)` clones every field but the sub builders
+            used_generic_param(ty, gpids.as_slice(), &mut path_with_params);
+        }
+        fields_builder.push(quote! {
+            #cfgs #ident: #builder_ty
+        });
+        merge_steps.extend(field.merge_step());
+        if let Some(peek_ty) = field.peek_ty() {
+            let doc = format!("the value set for `{field_name}`, if any");
+            peek_getters.push((
+                ident,
+                quote!(#cfgs #[doc = #doc]),
+                quote!(::core::option::Option<&#peek_ty>),
+            ));
+        }
+        // a typestate builder can't forget a required field its type says is set
+        if !(typestate && field.is_required()) && field.attrs.start_fn.is_none() {
+            let clear_name = format_ident!("clear_{field_name}", span = ident.span());
+            let doc = format!("forgets what was set for `{field_name}`");
+            methods_builder.push(
+                SetterFn {
+                    name: clear_name,
+                    attrs: quote!(#cfgs #[doc = #doc]),
+                    args: vec![],
+                    receiver: &setter_receiver,
+                    output: &setter_output,
+                    bounds: vec![],
+                    body: quote! {
+                        self.#ident = ::core::default::Default::default();
+                        self
+                    },
                 }
-                return None;
-            }) {
-                match get_each_method_name(attr, tokens) {
-                    Ok(each_method_name) => {
-                        if let Some(ty_inner) = is_vec(ty) {
-                            fields_builder.push(quote! {
-                                #ident: ::std::vec::Vec<#ty_inner>
-                            });
-                            methods_builder.push(quote! {
-                                pub fn #each_method_name(&mut self, v: #ty_inner) -> &mut Self {
-                                    self.#ident.push(v);
-                                    self
-                                }
-                            });
-                            build_internal.push(quote! {
-                                #ident: ::core::mem::take(&mut self.#ident)
-                            });
-                        } else {
-                            errors
-                                .push(Error::new_spanned(ty, "builder attr each without Vec type"))
+                .expand(&SetterAttrs::default(), &mut methods_names),
+            );
+        }
+        if !matches!(field.kind, FieldKind::SubBuilder(_)) && field.attrs.start_fn.is_none() {
+            let is_set_name = format_ident!("is_{field_name}_set", span = ident.span());
+            let doc = format!("whether `{field_name}` was set");
+            methods_builder.push(
+                SetterFn {
+                    name: is_set_name,
+                    attrs: quote!(#cfgs #[doc = #doc]),
+                    args: vec![],
+                    receiver: &quote!(&self),
+                    output: &quote!(bool),
+                    bounds: vec![],
+                    body: quote!(self.#ident.is_some()),
+                }
+                .expand(&SetterAttrs::default(), &mut methods_names),
+            );
+        }
+
+        let setter = struct_attrs.setter.with(&field.attrs.setter);
+        let setter_name = field.setter_name(&setter);
+        if let Some((_, name)) = &field.attrs.group {
+            if field.is_required() {
+                errors.push(Error::new_spanned(
+                    name,
+                    "fields of a group need to be `Option`, `each` or have a `default`",
+                ));
+            }
+            let group = match groups.iter().position(|group| group.name.value() == name.value()) {
+                Some(idx) => &mut groups[idx],
+                None => {
+                    groups.push(FieldGroup {
+                        name: name.clone(),
+                        members: vec![],
+                        exclusive: false,
+                        required: false,
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+            group.members.push((setter_name.unraw().to_string(), ident, cfgs.clone()));
+            group.exclusive |= field.attrs.exclusive;
+            group.required |= field.attrs.required;
+        }
+        match field.kind {
+            // a parameter of `builder(..)`, no setter
+            FieldKind::Plain if field.attrs.start_fn.is_some() => {
+                if typestate {
+                    state_idx += 1;
+                }
+            }
+            FieldKind::Each {
+                ref method,
+                collection,
+                ref item,
+                ..
+            } => {
+                let each_item = field.each_item_ty().unwrap();
+                let (args, entry) = if let EachItem::Pair(k, v) = item {
+                    if setter.try_into {
+                        errors.push(Error::new_spanned(
+                            method,
+                            "setter(try_into) is not supported for map entries",
+                        ));
+                    }
+                    (vec![(format_ident!("k"), quote!(#k)), (format_ident!("v"), quote!(#v))], quote!((k, v)))
+                } else {
+                    (vec![(format_ident!("v"), each_item.clone())], quote!(v))
+                };
+                // `HashSet<T>` only extends with `T: Hash + Eq`, which the struct may not require
+                let mut bounds = vec![];
+                if used_generic_param(collection, gpids.as_slice(), &mut HashSet::new()) {
+                    if let EachItem::Item = item {
+                        bounds.push(quote!(#collection: ::core::iter::IntoIterator));
+                    }
+                    bounds.push(quote!(#collection: ::core::default::Default + ::core::iter::Extend<#each_item>));
+                    build_bounds.push(quote!(#collection: ::core::default::Default));
+                    if field.merge_policy() == MergePolicy::Append {
+                        merge_bounds.push(quote! {
+                            #collection: ::core::default::Default
+                                + ::core::iter::IntoIterator
+                                + ::core::iter::Extend<<#collection as ::core::iter::IntoIterator>::Item>
+                        });
+                    }
+                }
+                methods_builder.push(
+                    SetterFn {
+                        name: method.clone(),
+                        attrs: cfg_docs.clone(),
+                        args,
+                        receiver: &setter_receiver,
+                        output: &setter_output,
+                        bounds: bounds.clone(),
+                        body: quote! {
+                            ::core::iter::Extend::extend(
+                                self.#ident.get_or_insert_with(::core::default::Default::default),
+                                ::core::iter::once(#entry),
+                            );
+                            self
+                        },
+                    }
+                    .expand(&setter, &mut methods_names),
+                );
+                let extend_name = format_ident!("extend_{field_name}", span = ident.span());
+                let doc = format!("adds every element of `iter` to `{field_name}`");
+                methods_builder.push(
+                    SetterFn {
+                        name: extend_name,
+                        attrs: quote!(#cfgs #[doc = #doc]),
+                        args: vec![(
+                            format_ident!("iter"),
+                            quote!(impl ::core::iter::IntoIterator<Item = #each_item>),
+                        )],
+                        receiver: &setter_receiver,
+                        output: &setter_output,
+                        bounds,
+                        body: quote! {
+                            ::core::iter::Extend::extend(
+                                self.#ident.get_or_insert_with(::core::default::Default::default),
+                                iter,
+                            );
+                            self
+                        },
+                    }
+                    .expand(&SetterAttrs::default(), &mut methods_names),
+                );
+                // `#[builder(each = "env")] env: Vec<String>`, the element setter takes the name
+                if *method != setter_name {
+                    methods_builder.push(
+                        SetterFn {
+                            name: setter_name,
+                            attrs: cfg_docs.clone(),
+                            args: vec![(format_ident!("v"), quote!(#collection))],
+                            receiver: &setter_receiver,
+                            output: &setter_output,
+                            bounds: vec![],
+                            body: quote! {
+                                self.#ident = ::core::option::Option::Some(v);
+                                self
+                            },
                         }
+                        .expand(&setter, &mut methods_names),
+                    );
+                }
+            }
+            FieldKind::Option(ty_inner) => {
+                methods_builder.push(
+                    SetterFn {
+                        name: setter_name,
+                        attrs: cfg_docs.clone(),
+                        args: vec![(format_ident!("v"), quote!(#ty_inner))],
+                        receiver: &setter_receiver,
+                        output: &setter_output,
+                        bounds: vec![],
+                        body: quote! {
+                            self.#ident = ::core::option::Option::Some(v);
+                            self
+                        },
                     }
-                    Err(err) => {
-                        errors.push(err);
+                    .expand(&setter, &mut methods_names),
+                );
+            }
+            FieldKind::Plain if typestate && field.is_required() => {
+                // moves every slot into the builder type with this field's state set
+                let set_state = builder_ty_with(
+                    states
+                        .iter()
+                        .enumerate()
+                        .map(|(n, s)| if n == state_idx { quote!(true) } else { quote!(#s) })
+                        .collect(),
+                );
+                let all_idents = &fields_ident;
+                let all_cfgs = &fields_cfgs;
+                methods_builder.push(
+                    SetterFn {
+                        name: setter_name,
+                        attrs: cfg_docs.clone(),
+                        args: vec![(format_ident!("v"), quote!(#ty))],
+                        receiver: &quote!(mut self),
+                        output: &set_state,
+                        bounds: vec![],
+                        body: quote! {
+                            self.#ident = ::core::option::Option::Some(v);
+                            #struct_builder_name {
+                                #(#all_cfgs #all_idents: self.#all_idents),*
+                            }
+                        },
+                    }
+                    .expand(&setter, &mut methods_names),
+                );
+                state_idx += 1;
+            }
+            FieldKind::SubBuilder(ref builder) => {
+                // the setters of the inner builder take `&mut self`, like the default pattern
+                let mut_name = format_ident!("{field_name}_mut", span = ident.span());
+                let doc = format!("the builder of `{field_name}`, to update it in place");
+                methods_builder.push(
+                    SetterFn {
+                        name: mut_name,
+                        attrs: quote!(#cfgs #[doc = #doc]),
+                        args: vec![],
+                        receiver: &quote!(&mut self),
+                        output: &quote!(&mut #builder),
+                        bounds: vec![],
+                        body: quote!(&mut self.#ident),
+                    }
+                    .expand(&SetterAttrs::default(), &mut methods_names),
+                );
+                methods_builder.push(
+                    SetterFn {
+                        name: setter_name,
+                        attrs: cfg_docs.clone(),
+                        args: vec![(
+                            format_ident!("f"),
+                            quote!(impl ::core::ops::FnOnce(&mut #builder) -> &mut #builder),
+                        )],
+                        receiver: &setter_receiver,
+                        output: &setter_output,
+                        bounds: vec![],
+                        body: quote! {
+                            f(&mut self.#ident);
+                            self
+                        },
                     }
+                    .expand(&SetterAttrs::default(), &mut methods_names),
+                );
+            }
+            FieldKind::Skip => unreachable!(),
+            FieldKind::Plain => {
+                methods_builder.push(
+                    SetterFn {
+                        name: setter_name,
+                        attrs: cfg_docs.clone(),
+                        args: vec![(format_ident!("v"), quote!(#ty))],
+                        receiver: &setter_receiver,
+                        output: &setter_output,
+                        bounds: vec![],
+                        body: quote! {
+                            self.#ident = ::core::option::Option::Some(v);
+                            self
+                        },
+                    }
+                    .expand(&setter, &mut methods_names),
+                );
+            }
+        }
+
+        if let Some(env) = &field.attrs.env {
+            if typestate {
+                // the type of the builder can't know whether the variable is set
+                errors.push(Error::new_spanned(env, "env isn't supported on typestate builders"));
+            }
+            if struct_attrs.crate_root == CrateRoot::Alloc {
+                // `::alloc` has no environment to read
+                errors.push(Error::new_spanned(env, "env needs `std`, not `crate_root = alloc`"));
+            }
+            let env_ty = field.env_ty().unwrap();
+            if used_generic_param(env_ty, gpids.as_slice(), &mut HashSet::new()) {
+                build_bounds.push(quote!(#env_ty: ::core::str::FromStr));
+                build_bounds.push(quote!(<#env_ty as ::core::str::FromStr>::Err: ::core::fmt::Display));
+            }
+        }
+        if field.is_required() {
+            let env_unset = field
+                .attrs
+                .env
+                .as_ref()
+                .map(|env| quote!(&& ::std::env::var_os(#env).is_none()));
+            build_checks.push(quote! {
+                #cfgs
+                if self.#ident.is_none() #env_unset {
+                    missing.push(stringify!(#field_name));
+                }
+            });
+            let env_unset = field.attrs.env.as_ref().map(|_| {
+                let env_value = field.env_value();
+                quote!(&& #env_value.is_none())
+            });
+            build_checks_env_read.push(quote! {
+                #cfgs
+                if self.#ident.is_none() #env_unset {
+                    missing.push(stringify!(#field_name));
+                }
+            });
+        }
+    }
+
+    for (ident, cfgs, output) in peek_getters {
+        if !methods_names.contains(ident) {
+            methods_builder.push(
+                SetterFn {
+                    name: ident.clone(),
+                    attrs: cfgs,
+                    args: vec![],
+                    receiver: &quote!(&self),
+                    output: &output,
+                    bounds: vec![],
+                    body: quote!(self.#ident.as_ref()),
                 }
-            } else if let Some(ty_inner) = is_option(ty) {
-                fields_builder.push(quote! {
-                    #ident: ::core::option::Option<#ty_inner>
+                .expand(&SetterAttrs::default(), &mut methods_names),
+            );
+        }
+    }
+    // what `build` would report as missing, without building, unless a setter took the name
+    let missing_fields = format_ident!("missing_fields");
+    if !methods_names.contains(&missing_fields) {
+        methods_builder.push(
+            SetterFn {
+                name: missing_fields,
+                attrs: quote!(#[doc = "the required fields `build` would report as missing"]),
+                args: vec![],
+                receiver: &quote!(&self),
+                output: &quote!(#root::vec::Vec<&'static str>),
+                bounds: vec![],
+                body: quote! {
+                    #[allow(unused_mut)]
+                    let mut missing = #root::vec::Vec::new();
+                    #(#build_checks)*
+                    missing
+                },
+            }
+            .expand(&SetterAttrs::default(), &mut methods_names),
+        );
+    }
+
+    // layered configuration, what `other` set wins
+    // `__merge` is what an outer builder calls, its field may have taken `merge`
+    let merge = format_ident!("merge");
+    let merge_names = if methods_names.contains(&merge) {
+        vec![format_ident!("__merge")]
+    } else {
+        vec![format_ident!("__merge"), merge]
+    };
+    for name in merge_names {
+        let attrs = if name == "__merge" {
+            quote!(#[doc(hidden)])
+        } else {
+            quote!(#[doc = "overlays the fields `other` set on this builder"])
+        };
+        methods_builder.push(
+            SetterFn {
+                name,
+                attrs,
+                args: vec![(format_ident!("other"), quote!(Self))],
+                receiver: &setter_receiver,
+                output: &setter_output,
+                bounds: merge_bounds.clone(),
+                body: quote! {
+                    #(#merge_steps)*
+                    self
+                },
+            }
+            .expand(&SetterAttrs::default(), &mut methods_names),
+        );
+    }
+
+    // private, what `build` and `merge` of this builder call before touching a sub builder
+    methods_builder.extend(fields.iter().filter_map(|field| field.sub_builder_needs_fn(pattern)));
+
+    // what `#[builder(sub_builder)]` in another builder requires, see `BuilderField::sub_build`,
+    // both traits always exist so a missing one is an unmet bound
+    let sub_builder_traits = [
+        (Pattern::Mutable, pattern != Pattern::Owned, "the `mutable` or `immutable` pattern"),
+        (Pattern::Immutable, pattern == Pattern::Immutable, "the `immutable` pattern, like its outer builder"),
+    ]
+    .into_iter()
+    .map(|(outer, implemented, needs)| {
+        let name = sub_builder_trait(&struct_builder_name, outer);
+        let message = format!("`{struct_builder_name}` needs {needs} to be a sub builder");
+        let implementation = implemented.then(|| {
+            quote! {
+                impl #builder_impl_generics #name for #struct_builder_name #builder_ty_generics #where_clause {}
+            }
+        });
+        quote! {
+            #[doc(hidden)]
+            #[diagnostic::on_unimplemented(message = #message)]
+            #vis trait #name {}
+            #implementation
+        }
+    })
+    .collect::<TokenStream>();
+
+    for (idx, name) in methods_names.iter().enumerate() {
+        if methods_names[..idx].contains(name) {
+            errors.push(Error::new(
+                name.span(),
+                format!("builder method `{name}` is generated more than once"),
+            ));
+        }
+    }
+
+    build_bounds.extend(
+        path_with_params
+            .iter()
+            .map(|p| quote!(#p: ::core::clone::Clone)),
+    );
+    let build_where = if build_bounds.is_empty() {
+        quote!()
+    } else {
+        quote!(where #(#build_bounds),*)
+    };
+
+    // typestate builders can't miss a field, their `build` only fails through validation
+    let mut error_variants = vec![];
+    let mut error_displays = vec![];
+    // what `__check_build` runs, `build` only goes on when all of it passes
+    // every variable is read and parsed once, for the missing fields and for the value
+    let mut check_steps = fields
+        .iter()
+        .filter_map(|field| field.env_step(&struct_builder_error_name))
+        .collect::<Vec<_>>();
+    let mut built_steps = vec![];
+    if !typestate {
+        error_variants.push(quote! {
+            /// names of the required fields which were never set
+            MissingFields(#root::vec::Vec<&'static str>)
+        });
+        error_displays.push(quote! {
+            Self::MissingFields(fields) => {
+                f.write_str("missing required fields:")?;
+                for (idx, field) in fields.iter().enumerate() {
+                    let sep = if idx == 0 { " " } else { ", " };
+                    ::core::write!(f, "{}`{}`", sep, field)?;
+                }
+                ::core::result::Result::Ok(())
+            }
+        });
+        check_steps.push(quote! {
+            #[allow(unused_mut)]
+            let mut missing = #root::vec::Vec::new();
+            #(#build_checks_env_read)*
+            if !missing.is_empty() {
+                return ::core::result::Result::Err(#struct_builder_error_name::MissingFields(missing));
+            }
+        });
+    }
+    if !groups.is_empty() {
+        error_variants.push(quote! {
+            /// more than one field of an `exclusive` or `required` group was set
+            GroupConflict {
+                /// the name of the group
+                group: &'static str,
+                /// the setters called
+                fields: #root::vec::Vec<&'static str>,
+            }
+        });
+        error_variants.push(quote! {
+            /// no field of a `required` group was set, `fields` lists the choices
+            GroupMissing {
+                /// the name of the group
+                group: &'static str,
+                /// the setters of the group
+                fields: #root::vec::Vec<&'static str>,
+            }
+        });
+        error_displays.push(quote! {
+            Self::GroupConflict { group, fields } => ::core::write!(
+                f,
+                "only one of `{}` can be set in group `{}`",
+                fields.join("`, `"),
+                group,
+            ),
+            Self::GroupMissing { group, fields } => ::core::write!(
+                f,
+                "one of `{}` is required in group `{}`",
+                fields.join("`, `"),
+                group,
+            ),
+        });
+    }
+    for group in &groups {
+        let FieldGroup {
+            name,
+            members,
+            exclusive,
+            required,
+        } = group;
+        let setters = members.iter().map(|(setter, _, _)| setter);
+        let idents = members.iter().map(|(_, ident, _)| ident);
+        let cfgs = members.iter().map(|(_, _, cfgs)| cfgs);
+        if !exclusive && !required {
+            errors.push(Error::new_spanned(
+                name,
+                "a group needs `exclusive` or `required` on one of its fields",
+            ));
+        }
+        // the names users call, so the error says which setters conflict
+        check_steps.push(quote! {
+            let mut set: #root::vec::Vec<&'static str> = #root::vec::Vec::new();
+            #(
+                #cfgs
+                if self.#idents.is_some() {
+                    set.push(#setters);
+                }
+            )*
+            if set.len() > 1 {
+                return ::core::result::Result::Err(#struct_builder_error_name::GroupConflict {
+                    group: #name,
+                    fields: set,
                 });
-                methods_builder.push(quote! {
-                    pub fn #ident(&mut self, v: #ty_inner) -> &mut Self {
-                        self.#ident = ::core::option::Option::Some(v);
-                        self
+            }
+        });
+        if *required {
+            let setters = members.iter().map(|(setter, _, _)| setter);
+            let cfgs = members.iter().map(|(_, _, cfgs)| cfgs);
+            check_steps.push(quote! {
+                if set.is_empty() {
+                    let mut fields: #root::vec::Vec<&'static str> = #root::vec::Vec::new();
+                    #(
+                        #cfgs
+                        fields.push(#setters);
+                    )*
+                    return ::core::result::Result::Err(#struct_builder_error_name::GroupMissing {
+                        group: #name,
+                        fields,
+                    });
+                }
+            });
+        }
+    }
+    let BuildFnAttrs {
+        validate,
+        validate_built,
+    } = &struct_attrs.build_fn;
+    if validate.is_some() || validate_built.is_some() {
+        error_variants.push(quote! {
+            /// the message of a failed `build_fn(validate)` or `build_fn(validate_built)`
+            Validation(#root::string::String)
+        });
+        error_displays.push(quote! {
+            Self::Validation(message) => f.write_str(message),
+        });
+    }
+    let has_sub_builder = fields
+        .iter()
+        .any(|field| matches!(field.kind, FieldKind::SubBuilder(_)));
+    let mut into_sub_builder_error = vec![];
+    if has_sub_builder {
+        error_variants.push(quote! {
+            /// a sub builder failed, `path` leads to its field like `server.tls`
+            SubBuilder {
+                /// the fields leading to the sub builder which failed
+                path: #root::string::String,
+                /// what its error says
+                message: #root::string::String,
+            }
+        });
+        error_displays.push(quote! {
+            Self::SubBuilder { path, message } => ::core::write!(f, "`{}`: {}", path, message),
+        });
+        into_sub_builder_error.push(quote! {
+            Self::SubBuilder { path, message } => (#root::format!("{}.{}", field, path), message),
+        });
+    }
+    if fields.iter().any(|field| field.attrs.env.is_some()) {
+        error_variants.push(quote! {
+            /// the environment variable `var` of an `env` field couldn't be parsed
+            Env {
+                /// the name of the variable
+                var: &'static str,
+                /// why its value didn't parse
+                message: #root::string::String,
+            }
+        });
+        error_displays.push(quote! {
+            Self::Env { var, message } => ::core::write!(f, "environment variable `{}`: {}", var, message),
+        });
+    }
+    if let Some(validate) = validate {
+        check_steps.push(quote! {
+            // `build` runs this inline, where `self` may be a value or a `&mut`
+            let __builder: &Self = &self;
+            if let ::core::result::Result::Err(err) = #validate(__builder) {
+                return ::core::result::Result::Err(#struct_builder_error_name::Validation(
+                    #root::string::ToString::to_string(&err),
+                ));
+            }
+        });
+    }
+    // every sub builder is checked before any of them is built and drained
+    check_steps.extend(
+        fields
+            .iter()
+            .filter_map(|field| field.sub_check(&struct_builder_error_name)),
+    );
+    // before taking anything, so a failed check leaves the builder untouched, inline so the
+    // parsed variables are what `build` uses
+    let mut build_steps = check_steps.clone();
+    let mut in_place_steps = check_steps.clone();
+    in_place_steps.extend(
+        fields
+            .iter()
+            .filter_map(|field| field.sub_build(Pattern::Mutable, &struct_builder_error_name)),
+    );
+    build_steps.extend(
+        fields
+            .iter()
+            .filter_map(|field| field.sub_build(pattern, &struct_builder_error_name)),
+    );
+    if let Some(validate_built) = validate_built {
+        // not with `mutable`, the builder has already given up its values here
+        built_steps.push(quote! {
+            if let ::core::result::Result::Err(err) = #validate_built(&value) {
+                return ::core::result::Result::Err(#struct_builder_error_name::Validation(
+                    #root::string::ToString::to_string(&err),
+                ));
+            }
+        });
+    }
+
+    let (build_output, build_value, check_fn) = if error_variants.is_empty() {
+        // typestate, only reachable once every required field is set
+        (quote!(#struct_name #ty_generics), quote!(value), quote!())
+    } else {
+        (
+            quote!(::core::result::Result<#struct_name #ty_generics, #struct_builder_error_name>),
+            quote!(::core::result::Result::Ok(value)),
+            quote! {
+                // what `build` checks, an outer builder runs it before building any sub builder
+                #[doc(hidden)]
+                pub fn __check_build(&self) -> ::core::result::Result<(), #struct_builder_error_name>
+                    #build_where
+                {
+                    #(#check_steps)*
+                    ::core::result::Result::Ok(())
+                }
+            },
+        )
+    };
+    let build_doc = format!("builds [`{target}`] from the fields set so far");
+    let build_fn = quote! {
+        #check_fn
+
+        #[doc = #build_doc]
+        pub fn build(#build_receiver) -> #build_output
+            #build_where
+        {
+            #(#build_steps)*
+            let value = #constructor {
+                #(#build_internal),*
+            };
+            #(#built_steps)*
+            #build_value
+        }
+    };
+    // `build(self)` gives up an owned builder even when it fails, `try_build` hands it back
+    let try_build_fn = if pattern == Pattern::Owned && !error_variants.is_empty() {
+        // a failed `validate_built` or sub builder comes after values were taken, a copy
+        // taken beforehand is what goes back then
+        let (backup, failed, try_build_where) = if validate_built.is_some() || has_sub_builder {
+            (
+                quote!(let backup = ::core::clone::Clone::clone(&self);),
+                quote!(backup),
+                quote!(where #(#build_bounds,)* for<'__a> Self: ::core::clone::Clone),
+            )
+        } else {
+            (quote!(), quote!(self), build_where.clone())
+        };
+        quote! {
+            /// like `build`, but hands the builder back with the error when it fails
+            pub fn try_build(
+                mut self,
+            ) -> ::core::result::Result<#struct_name #ty_generics, (#struct_builder_error_name, Self)>
+                #try_build_where
+            {
+                #backup
+                match self.__build_in_place() {
+                    ::core::result::Result::Ok(value) => ::core::result::Result::Ok(value),
+                    ::core::result::Result::Err(err) => ::core::result::Result::Err((err, #failed)),
+                }
+            }
+
+            fn __build_in_place(&mut self) -> #build_output
+                #build_where
+            {
+                #(#in_place_steps)*
+                let value = #constructor {
+                    #(#build_internal_in_place),*
+                };
+                #(#built_steps)*
+                #build_value
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let error_type = if error_variants.is_empty() {
+        quote!()
+    } else {
+        let doc = format!("why building [`{target}`] failed");
+        quote! {
+            #[doc = #doc]
+            #[derive(::core::fmt::Debug, ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            #vis enum #struct_builder_error_name {
+                #(#error_variants),*
+            }
+
+            impl ::core::fmt::Display for #struct_builder_error_name {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#error_displays)*
                     }
-                });
-                build_internal.push(quote! {
-                    #ident: ::core::mem::take(&mut self.#ident)
-                });
-            } else {
-                fields_builder.push(quote! {
-                    #ident: ::core::option::Option<#ty>
-                });
-                methods_builder.push(quote! {
-                    pub fn #ident(&mut self, v: #ty) -> &mut Self {
-                        self.#ident = ::core::option::Option::Some(v);
-                        self
+                }
+            }
+
+            impl ::core::error::Error for #struct_builder_error_name {}
+
+            impl #struct_builder_error_name {
+                // the path and message when this is the error of a sub builder
+                #[doc(hidden)]
+                pub fn __into_sub_builder_error(
+                    self,
+                    field: &str,
+                ) -> (#root::string::String, #root::string::String) {
+                    match self {
+                        #(#into_sub_builder_error)*
+                        other => (
+                            #root::string::ToString::to_string(field),
+                            #root::string::ToString::to_string(&other),
+                        ),
                     }
-                });
-                build_internal.push(quote! {
-                    #ident: self.#ident.take()?
-                });
+                }
+            }
+        }
+    };
+
+    // a builder made without the `start_fn` fields would have them missing
+    let default_impl = if start_params.is_empty() {
+        quote! {
+            // not derived, that would require every type parameter to be `Default`
+            impl #impl_generics ::core::default::Default for #builder_initial #where_clause {
+                fn default() -> Self {
+                    Self {
+                        #(#fields_cfgs #fields_ident: ::core::default::Default::default()),*
+                    }
+                }
             }
         }
     } else {
-        errors.push(Error::new_spanned(&input, "should be struct"));
-    }
+        quote!()
+    };
 
-    let expanded = if errors.is_empty() {
+    // `#[builder(debug)]`, redacted fields print without their value
+    let debug_impl = if struct_attrs.debug.is_some() {
+        let debug_fields = fields.iter().filter_map(BuilderField::debug_field);
+        let mut debug_generics = builder_generics.clone();
+        let debug_where = debug_generics.make_where_clause();
+        for field in &fields {
+            // the value in the slot, or the whole sub builder
+            let Some(debug_ty) = field.peek_ty().or_else(|| field.builder_ty()) else {
+                continue;
+            };
+            if field.attrs.redact || !used_generic_param(field.ty, gpids.as_slice(), &mut HashSet::new()) {
+                continue;
+            }
+            debug_where.predicates.push(parse_quote!(#debug_ty: ::core::fmt::Debug));
+        }
+        let (debug_impl_generics, _, debug_where_clause) = debug_generics.split_for_impl();
+        let name = struct_builder_name.to_string();
         quote! {
-            impl #struct_name {
-                pub fn builder() -> #struct_builder_name {
-                    <#struct_builder_name as ::core::default::Default>::default()
+            impl #debug_impl_generics ::core::fmt::Debug for #struct_builder_name #builder_ty_generics #debug_where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut s = f.debug_struct(#name);
+                    #(#debug_fields)*
+                    s.finish()
                 }
             }
+        }
+    } else {
+        quote!()
+    };
 
-            #[derive(Default)]
-            #vis struct #struct_builder_name {
-                #(#fields_builder),*
+    // opt-in, taking the struct apart fails for one implementing `Drop`
+    let round_trip = if let Some(to_builder) = &struct_attrs.to_builder {
+        if variant.is_some() {
+            // a variant builder can't be made from any value of the enum
+            errors.push(Error::new_spanned(to_builder, "to_builder only applies to structs"));
+        }
+        quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                // `for<'__a>` keeps the bound from failing the impl when `Self` isn't `Clone`
+                /// a builder with every field of `self` set
+                #vis fn to_builder(&self) -> #builder_final
+                where
+                    for<'__a> Self: ::core::clone::Clone,
+                {
+                    ::core::convert::From::from(::core::clone::Clone::clone(self))
+                }
+            }
+
+            // every field is set, so typestate builders start out complete
+            impl #impl_generics ::core::convert::From<#struct_name #ty_generics> for #builder_final #where_clause {
+                fn from(value: #struct_name #ty_generics) -> Self {
+                    let #struct_name { #(#from_members,)* .. } = value;
+                    Self {
+                        #(#from_slots),*
+                    }
+                }
             }
+        }
+    } else {
+        quote!()
+    };
 
-            impl #struct_builder_name {
-                pub fn build(&mut self) -> ::core::option::Option<#struct_name> {
-                    Some(#struct_name {
-                        #(#build_internal),*
-                    })
+    let builder_fn_doc = format!("starts building [`{target}`]");
+    let builder_doc = format!("builds [`{target}`] one field at a time");
+    if errors.is_empty() {
+        quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #[doc = #builder_fn_doc]
+                #vis fn #builder_fn(#(#start_params),*) -> #builder_initial {
+                    #struct_builder_name {
+                        #(#fields_cfgs #fields_ident: #fields_init),*
+                    }
                 }
+            }
+
+            #round_trip
 
+            #[doc = #builder_doc]
+            #[derive(#(#derives),*)]
+            #vis struct #struct_builder_name #builder_generics #where_clause {
+                #(#fields_builder),*
+            }
+
+            #default_impl
+
+            #debug_impl
+
+            #sub_builder_traits
+
+            #error_type
+
+            impl #impl_generics #builder_final #where_clause {
+                #build_fn
+                #try_build_fn
+            }
+
+            impl #builder_impl_generics #struct_builder_name #builder_ty_generics #where_clause {
                 #(#methods_builder)*
             }
         }
@@ -128,49 +1167,117 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         quote! {
             #(#errors)*
         }
-    };
-    proc_macro::TokenStream::from(expanded)
+    }
 }
 
-// find `#[builder(each = "arg")] args: Vec<String>`, `#[builder(each = arg)]`
-fn get_each_method_name(attr: &Attribute, tokens: &TokenStream) -> Result<Ident> {
-    let attr_id_each = format_ident!("each");
-    let mut tokens_iter = tokens.to_token_stream().into_iter();
-    if let Some(TokenTree::Ident(id_each)) = tokens_iter.next()
-        && id_each == attr_id_each
-        && let Some(TokenTree::Punct(punct_eq)) = tokens_iter.next()
-        && punct_eq.as_char() == '='
-        && let Some(method_name) = tokens_iter.next()
-    {
-        if let TokenTree::Literal(method_name) = method_name {
-            if let Ok(Lit::Str(s)) = syn::parse_str::<Lit>(&method_name.to_string()) {
-                if let Ok(mut id) = syn::parse_str::<Ident>(&s.value()) {
-                    // `#[builder(each = "arg")]` 判断了 "arg" 是否有效标识符
-                    id.set_span(method_name.span());
-                    return Ok(id);
-                } else {
-                    return Err(
-                        Error::new_spanned(method_name, "not a valid ident")
-                    );
+// `pub fn name(self, v: ty) -> output { body }`
+struct SetterFn<'a> {
+    name: Ident,
+    // `#[cfg]` and doc comments of the field
+    attrs: TokenStream,
+    args: Vec<(Ident, TokenStream)>,
+    receiver: &'a TokenStream,
+    output: &'a TokenStream,
+    bounds: Vec<TokenStream>,
+    body: TokenStream,
+}
+
+impl SetterFn<'_> {
+    // also `try_name` for `setter(try_into)`, every generated name goes into `names`
+    fn expand(self, setter: &SetterAttrs, names: &mut Vec<Ident>) -> TokenStream {
+        let SetterFn {
+            name,
+            attrs,
+            args,
+            receiver,
+            output,
+            bounds,
+            body,
+        } = self;
+        let where_clause = if bounds.is_empty() {
+            quote!()
+        } else {
+            quote!(where #(#bounds),*)
+        };
+        let arg_names = args.iter().map(|(arg, _)| arg);
+        let arg_tys = args.iter().map(|(_, ty)| ty);
+        let mut setters = if setter.into {
+            let arg_names2 = arg_names.clone();
+            let arg_tys2 = arg_tys.clone();
+            quote! {
+                #attrs
+                pub fn #name(#receiver, #(#arg_names: impl ::core::convert::Into<#arg_tys>),*) -> #output
+                    #where_clause
+                {
+                    #(let #arg_names2: #arg_tys2 = ::core::convert::Into::into(#arg_names2);)*
+                    #body
                 }
-            } else {
-                return Err(
-                    Error::new_spanned(method_name, "lit is not str")
-                );
             }
-        } else if let TokenTree::Ident(method_name) = method_name {
-            // `#[builder(each = arg)]`
-            return Ok(method_name);
         } else {
-            return Err(
-                Error::new_spanned(method_name, "not lit str nor ident")
-            )
+            quote! {
+                #attrs
+                pub fn #name(#receiver, #(#arg_names: #arg_tys),*) -> #output
+                    #where_clause
+                {
+                    #body
+                }
+            }
+        };
+        if setter.try_into
+            && let [(arg, ty)] = args.as_slice()
+        {
+            let try_name = format_ident!("try_{}", name.unraw(), span = name.span());
+            setters.extend(quote! {
+                #attrs
+                pub fn #try_name<__V: ::core::convert::TryInto<#ty>>(#receiver, #arg: __V)
+                    -> ::core::result::Result<#output, <__V as ::core::convert::TryInto<#ty>>::Error>
+                    #where_clause
+                {
+                    let #arg: #ty = ::core::convert::TryInto::try_into(#arg)?;
+                    ::core::result::Result::Ok({ #body })
+                }
+            });
+            names.push(try_name);
         }
-    } else {
-        return Err(
-            Error::new_spanned(&attr.meta, "expected `builder(each = \"...\")`")
-        )
+        names.push(name);
+        setters
+    }
+}
+
+// `#[builder(group = "auth")]` and the fields in it
+struct FieldGroup<'a> {
+    name: LitStr,
+    // the setter, the slot and the `#[cfg]` of each field
+    members: Vec<(String, &'a Ident, TokenStream)>,
+    exclusive: bool,
+    required: bool,
+}
+
+// whether `ident` appears anywhere in `tokens`
+fn mentions(tokens: &TokenStream, ident: &Ident) -> bool {
+    tokens.clone().into_iter().any(|tt| match tt {
+        TokenTree::Ident(i) => i == *ident,
+        TokenTree::Group(g) => mentions(&g.stream(), ident),
+        _ => false,
+    })
+}
+
+// `BigCircle` to `big_circle`, a run of capitals is one word: `HTTPRequest` to `http_request`
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        let starts_word = idx != 0
+            && match chars[idx - 1] {
+                prev if prev.is_uppercase() => chars.get(idx + 1).is_some_and(|next| next.is_lowercase()),
+                prev => prev.is_lowercase() || prev.is_numeric(),
+            };
+        if c.is_uppercase() && starts_word {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
     }
+    snake
 }
 
 #[allow(dead_code)]
@@ -196,17 +1303,48 @@ mod showcase {
         executable: ::core::option::Option<String>,
         vec_option: ::core::option::Option<Vec<i32>>,
         current_dir: ::core::option::Option<String>,
-        args: ::std::vec::Vec<String>,
-        env: ::std::vec::Vec<String>,
+        args: ::core::option::Option<Vec<String>>,
+        env: ::core::option::Option<Vec<String>>,
+    }
+    #[derive(::core::fmt::Debug, ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+    pub enum CommandBuilderError {
+        /// names of the required fields which were never set
+        MissingFields(::std::vec::Vec<&'static str>),
     }
+    impl ::core::fmt::Display for CommandBuilderError {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            match self {
+                Self::MissingFields(fields) => {
+                    f.write_str("missing required fields:")?;
+                    for (idx, field) in fields.iter().enumerate() {
+                        let sep = if idx == 0 { " " } else { ", " };
+                        ::core::write!(f, "{}`{}`", sep, field)?;
+                    }
+                    ::core::result::Result::Ok(())
+                }
+            }
+        }
+    }
+    impl ::core::error::Error for CommandBuilderError {}
     impl CommandBuilder {
-        pub fn build(&mut self) -> ::core::option::Option<Command> {
-            Some(Command {
-                executable: self.executable.take()?,
-                vec_option: self.vec_option.take()?,
+        pub fn build(&mut self) -> ::core::result::Result<Command, CommandBuilderError> {
+            #[allow(unused_mut)]
+            let mut missing = ::std::vec::Vec::new();
+            if self.executable.is_none() {
+                missing.push("executable");
+            }
+            if self.vec_option.is_none() {
+                missing.push("vec_option");
+            }
+            if !missing.is_empty() {
+                return ::core::result::Result::Err(CommandBuilderError::MissingFields(missing));
+            }
+            ::core::result::Result::Ok(Command {
+                executable: self.executable.take().unwrap(),
+                vec_option: self.vec_option.take().unwrap(),
                 current_dir: ::core::mem::take(&mut self.current_dir),
-                args: ::core::mem::take(&mut self.args),
-                env: ::core::mem::take(&mut self.env),
+                args: ::core::mem::take(&mut self.args).unwrap_or_default(),
+                env: ::core::mem::take(&mut self.env).unwrap_or_default(),
             })
         }
         pub fn executable(&mut self, v: String) -> &mut Self {
@@ -222,12 +1360,19 @@ mod showcase {
             self
         }
         pub fn arg(&mut self, v: String) -> &mut Self {
-            self.args.push(v);
+            ::core::iter::Extend::extend(
+                self.args.get_or_insert_with(::core::default::Default::default),
+                ::core::iter::once(v),
+            );
             self
         }
         pub fn env(&mut self, v: String) -> &mut Self {
-            self.env.push(v);
+            ::core::iter::Extend::extend(
+                self.env.get_or_insert_with(::core::default::Default::default),
+                ::core::iter::once(v),
+            );
             self
         }
     }
 }
+
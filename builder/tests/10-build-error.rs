@@ -0,0 +1,43 @@
+// A failed build reports which required fields were never set instead of
+// returning a bare `None`.
+//
+// The generated `CommandBuilderError` lists every missing field, implements
+// Display and std::error::Error, and the builder is left untouched so the
+// caller can fill in the missing fields and try again.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: String,
+    env: Option<String>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.arg("build".to_owned());
+
+    let err = match builder.build() {
+        Ok(_) => panic!("build should fail"),
+        Err(err) => err,
+    };
+    assert_eq!(
+        err,
+        CommandBuilderError::MissingFields(vec!["executable", "current_dir"])
+    );
+    assert_eq!(
+        err.to_string(),
+        "missing required fields: `executable`, `current_dir`"
+    );
+    let _: Box<dyn std::error::Error> = Box::new(err);
+
+    builder.executable("cargo".to_owned());
+    builder.current_dir("..".to_owned());
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert!(command.env.is_none());
+}
@@ -0,0 +1,109 @@
+// `#[builder(pattern = "...")]` on the struct picks how `build` treats the
+// builder.
+//
+//   - `mutable` (the default): setters and `build` take `&mut self`, and a
+//     successful build moves the values out of the builder.
+//   - `immutable`: setters take `&mut self`, `build(&self)` clones the values,
+//     so one builder can serve as a template for many values.
+//   - `owned`: setters and `build` take `self` by value. `try_build` hands
+//     the builder back along with the error when it fails.
+//
+// In every mode a failed build leaves the builder exactly as it was. An owned
+// builder checked by `validate_built` or holding sub builders is copied before
+// values are taken out, so its `try_build` needs the builder to be `Clone`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = owned)]
+pub struct Owned {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct Mutable {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: String,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", derive(Clone), build_fn(validate_built = "Range::check"))]
+pub struct Range {
+    start: u32,
+    #[builder(each = "step")]
+    steps: Vec<u32>,
+    end: u32,
+}
+
+impl Range {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.start > self.end {
+            Err("start is after end")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let mut template = Command::builder();
+    template.arg("build".to_owned()).current_dir("..".to_owned());
+    assert!(template.build().is_err());
+
+    template.executable("cargo".to_owned());
+    let first = template.build().unwrap_or_else(|_| panic!());
+    let second = template.build().unwrap_or_else(|_| panic!());
+    assert_eq!(first.args, vec!["build"]);
+    assert_eq!(second.args, vec!["build"]);
+    assert_eq!(second.current_dir.as_deref(), Some(".."));
+
+    let owned = Owned::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(owned.executable, "cargo");
+    let owned = Owned::builder().arg("build".to_owned());
+    assert_eq!(owned.missing_fields(), vec!["executable"]);
+    let owned = owned.executable("cargo".to_owned());
+    assert!(owned.missing_fields().is_empty());
+    assert_eq!(owned.build().unwrap_or_else(|_| panic!()).args, vec!["build"]);
+    assert!(Owned::builder().arg("build".to_owned()).build().is_err());
+
+    let Err((err, owned)) = Owned::builder().arg("build".to_owned()).try_build() else {
+        panic!();
+    };
+    assert_eq!(err, OwnedBuilderError::MissingFields(vec!["executable"]));
+    let Ok(owned) = owned.executable("cargo".to_owned()).try_build() else {
+        panic!();
+    };
+    assert_eq!(owned.args, vec!["build"]);
+
+    let Err((err, range)) = Range::builder().start(2).step(1).end(1).try_build() else {
+        panic!();
+    };
+    assert_eq!(err.to_string(), "start is after end");
+    let range = range.start(0).try_build().unwrap_or_else(|_| panic!());
+    assert_eq!(range.steps, vec![1]);
+
+    let mut builder = Mutable::builder();
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+    assert!(builder.build().is_err());
+    builder.current_dir("..".to_owned());
+    let mutable = builder.build().unwrap_or_else(|_| panic!());
+    assert_eq!(mutable.executable, "cargo");
+    assert_eq!(mutable.args, vec!["build"]);
+}
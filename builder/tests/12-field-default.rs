@@ -0,0 +1,53 @@
+// Fields marked `#[builder(default)]` become optional in the builder and fall
+// back to `Default::default()` when their setter was never called.
+// `#[builder(default = expr)]` uses the given expression instead. Either way
+// the default is evaluated inside `build`, and only when it is needed.
+
+use derive_builder::Builder;
+use std::cell::Cell;
+
+thread_local! {
+    static DEFAULTS_EVALUATED: Cell<usize> = Cell::new(0);
+}
+
+fn default_dir() -> String {
+    DEFAULTS_EVALUATED.with(|c| c.set(c.get() + 1));
+    "/".to_owned()
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = default_dir())]
+    current_dir: String,
+    #[builder(default = Some(8))]
+    jobs: Option<u32>,
+    #[builder(each = "env", default = vec!["PATH".to_owned()])]
+    envs: Vec<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert!(command.args.is_empty());
+    assert_eq!(command.current_dir, "/");
+    assert_eq!(command.jobs, Some(8));
+    assert_eq!(command.envs, vec!["PATH"]);
+    assert_eq!(DEFAULTS_EVALUATED.with(Cell::get), 1);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .jobs(2)
+        .env("HOME".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.jobs, Some(2));
+    assert_eq!(command.envs, vec!["HOME"]);
+    assert_eq!(DEFAULTS_EVALUATED.with(Cell::get), 1);
+}
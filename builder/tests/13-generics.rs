@@ -0,0 +1,60 @@
+// The builder carries every lifetime, type and const parameter of the struct
+// along with its where-clauses.
+//
+// No `T: Default` bound is required to create the builder. A builder with
+// `pattern = "immutable"` clones the fields in `build`, so its `build` is only
+// available when the types mentioning a type parameter are `Clone`, which is
+// inferred the same way `derive(CustomDebug)` infers its `Debug` bounds.
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+pub trait Codec {
+    type Frame;
+}
+
+pub struct Json;
+
+impl Codec for Json {
+    type Frame = String;
+}
+
+#[derive(Builder)]
+pub struct Request<'a, T: Codec, const N: usize>
+where
+    T::Frame: Debug,
+{
+    name: &'a str,
+    codec: T,
+    #[builder(each = "frame")]
+    frames: Vec<T::Frame>,
+    checksum: Option<[u8; N]>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Template<T> {
+    value: T,
+    #[builder(default)]
+    fallback: Vec<T>,
+}
+
+fn main() {
+    let name = "status".to_owned();
+    let request = Request::<Json, 4>::builder()
+        .name(&name)
+        .codec(Json)
+        .frame("hello".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(request.name, "status");
+    assert_eq!(request.frames, vec!["hello"]);
+    assert_eq!(request.checksum, None);
+
+    let mut template = Template::builder();
+    template.value(1);
+    let first = template.build().unwrap_or_else(|_| panic!());
+    let second = template.build().unwrap_or_else(|_| panic!());
+    assert_eq!(first.value + second.value, 2);
+    assert!(second.fallback.is_empty());
+}
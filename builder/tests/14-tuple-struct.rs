@@ -0,0 +1,44 @@
+// Tuple structs get one setter per positional field, named `_0`, `_1`, ...
+// unless the field is given a name with `#[builder(name = "...")]`. The
+// builder of a unit struct has no setters and always builds.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Port(u16);
+
+#[derive(Builder)]
+pub struct Endpoint(
+    #[builder(name = "host")] String,
+    u16,
+    #[builder(each = "path_segment")] Vec<String>,
+    Option<String>,
+);
+
+#[derive(Builder)]
+pub struct Marker;
+
+fn main() {
+    let port = Port::builder()._0(8080).build().unwrap_or_else(|_| panic!());
+    assert_eq!(port.0, 8080);
+
+    let err = Endpoint::builder().path_segment("api".to_owned()).build();
+    match err {
+        Err(EndpointBuilderError::MissingFields(fields)) => assert_eq!(fields, vec!["host", "_1"]),
+        _ => panic!(),
+    }
+
+    let endpoint = Endpoint::builder()
+        .host("localhost".to_owned())
+        ._1(80)
+        .path_segment("api".to_owned())
+        .path_segment("v1".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(endpoint.0, "localhost");
+    assert_eq!(endpoint.1, 80);
+    assert_eq!(endpoint.2, vec!["api", "v1"]);
+    assert_eq!(endpoint.3, None);
+
+    let Marker = Marker::builder().build().unwrap_or_else(|_| panic!());
+}
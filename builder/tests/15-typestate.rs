@@ -0,0 +1,43 @@
+// With `#[builder(typestate)]` the builder records in its type which required
+// fields have been set. `build` only exists once all of them are, and then it
+// returns the struct directly instead of a `Result`.
+//
+// Setters take the builder by value since setting a required field changes
+// the type of the builder. Optional and `each` fields can be set in any state.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command<'a, T> {
+    executable: &'a str,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default)]
+    jobs: u32,
+    extra: T,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .extra(())
+        .current_dir("..".to_owned())
+        .executable("cargo")
+        .arg("--release".to_owned())
+        .build();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.jobs, 0);
+
+    // setting a required field again keeps the latest value
+    let command = Command::builder()
+        .executable("cargo")
+        .extra(1)
+        .executable("rustc")
+        .build();
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.extra, 1);
+}
@@ -0,0 +1,19 @@
+// A typestate builder without every required field set has no `build` method.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .arg("build".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
@@ -0,0 +1,56 @@
+// `#[builder(setter(into))]` makes setters take `impl Into<T>`, so callers can
+// pass `"ls"` to a `String` field. `#[builder(setter(try_into))]` additionally
+// generates `try_x(v: impl TryInto<T>)` which returns the conversion error.
+//
+// Both may be given on the struct to apply to every setter, or on a single
+// field, and they apply to the element setters of `each` fields as well.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<PathBuf>,
+    #[builder(setter(try_into), default)]
+    jobs: u8,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Limits {
+    #[builder(setter(into, try_into))]
+    max_files: u16,
+    #[builder(each = "port", setter(try_into))]
+    ports: Vec<u16>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir("..");
+    assert!(builder.try_jobs(1000).is_err());
+    builder.try_jobs(8u64).unwrap();
+    let command = builder.build().unwrap_or_else(|_| panic!());
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, Some(PathBuf::from("..")));
+    assert_eq!(command.jobs, 8);
+
+    let limits = Limits::builder()
+        .max_files(100u8)
+        .try_port(8080i32)
+        .unwrap()
+        .port(443)
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(limits.max_files, 100);
+    assert_eq!(limits.ports, vec![8080, 443]);
+    assert!(Limits::builder().try_max_files(-1).is_err());
+}
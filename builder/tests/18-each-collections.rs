@@ -0,0 +1,77 @@
+// `#[builder(each = "...")]` works for any collection that implements
+// `Extend` and `Default`, not only `Vec`.
+//
+// The element setter of a `HashMap` or `BTreeMap` field takes the key and the
+// value, the element setter of `HashSet`, `BTreeSet`, `VecDeque`, ... takes one
+// element, and any other collection takes its `IntoIterator::Item`. An
+// `Option<Vec<T>>` field stays `None` until the first element is added.
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+#[derive(Default)]
+pub struct Words(String);
+
+impl Extend<char> for Words {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Words {
+    type Item = char;
+    type IntoIter = std::vec::IntoIter<char>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.chars().collect::<Vec<_>>().into_iter()
+    }
+}
+
+#[derive(Builder)]
+pub struct Request<K> {
+    #[builder(each = "header")]
+    headers: HashMap<String, String>,
+    #[builder(each = "cookie")]
+    cookies: BTreeMap<K, u32>,
+    #[builder(each = "tag")]
+    tags: HashSet<K>,
+    #[builder(each = "flag")]
+    flags: BTreeSet<char>,
+    #[builder(each = "retry")]
+    retries: VecDeque<u8>,
+    #[builder(each = "letter")]
+    letters: Words,
+    #[builder(each = "arg")]
+    args: Option<Vec<String>>,
+}
+
+fn build<K: Ord + Hash>(builder: &mut RequestBuilder<K>) -> Request<K> {
+    builder.build().unwrap_or_else(|_| panic!())
+}
+
+fn main() {
+    let mut builder = Request::builder();
+    builder
+        .header("Accept".to_owned(), "*/*".to_owned())
+        .cookie("session", 1)
+        .tag("a")
+        .tag("a")
+        .flag('x')
+        .retry(3)
+        .retry(5)
+        .letter('h')
+        .letter('i');
+    let request = build(&mut builder);
+    assert_eq!(request.headers["Accept"], "*/*");
+    assert_eq!(request.cookies["session"], 1);
+    assert_eq!(request.tags.len(), 1);
+    assert!(request.flags.contains(&'x'));
+    assert_eq!(request.retries, [3, 5]);
+    assert_eq!(request.letters.0, "hi");
+    assert_eq!(request.args, None);
+
+    let request = build(Request::<u8>::builder().arg("-v".to_owned()));
+    assert_eq!(request.args, Some(vec!["-v".to_owned()]));
+    assert!(request.headers.is_empty());
+}
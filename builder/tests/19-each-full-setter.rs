@@ -0,0 +1,41 @@
+// Besides the one-element-at-a-time setter, an `each` field keeps a setter
+// taking the whole collection, named after the field, and gets an
+// `extend_<field>` method taking any `IntoIterator` of elements.
+//
+// When the `each` name is the field name, the element setter takes the name
+// and there is no whole-collection setter. Any other clash between generated
+// method names is reported as an error.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .extend_args(vec!["--release".to_owned(), "--locked".to_owned()])
+        .env("A=1".to_owned())
+        .extend_env(["B=2".to_owned()])
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(command.args, vec!["build", "--release", "--locked"]);
+    assert_eq!(command.env, vec!["A=1", "B=2"]);
+
+    // the whole-collection setter replaces what was added before
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .args(vec!["test".to_owned()])
+        .arg("--all".to_owned())
+        .build()
+        .unwrap_or_else(|_| panic!());
+    assert_eq!(command.args, vec!["test", "--all"]);
+}
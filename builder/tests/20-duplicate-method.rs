@@ -0,0 +1,13 @@
+// Two fields must not generate a builder method with the same name.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "env")]
+    envs: Vec<String>,
+    env: Option<String>,
+}
+
+fn main() {}
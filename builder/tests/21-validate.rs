@@ -0,0 +1,67 @@
+// `#[builder(build_fn(validate = path))]` names a function which `build` calls
+// with the builder once every required field is set, before anything is
+// taken out of the builder. `#[builder(build_fn(validate_built = path))]`
+// names a function which is called with the built value instead. The default
+// `mutable` builder has given up its values by then, so `validate_built`
+// needs the `immutable` or `owned` pattern.
+//
+// Either function returns `Result<(), E>` for any `E: Display`, and its error
+// ends up in the `Validation` variant of the build error.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(build_fn(validate = check_tls))]
+pub struct Server {
+    host: String,
+    port: Option<u16>,
+    #[builder(default)]
+    tls: bool,
+}
+
+fn check_tls(builder: &ServerBuilder) -> Result<(), String> {
+    if builder.tls == Some(true) && builder.port.is_none() {
+        return Err("port must be set when tls is on".to_owned());
+    }
+    Ok(())
+}
+
+#[derive(Builder)]
+#[builder(typestate, build_fn(validate_built = "Range::check"))]
+pub struct Range {
+    start: u32,
+    end: u32,
+}
+
+impl Range {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.start > self.end {
+            Err("start is after end")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.host("localhost".to_owned()).tls(true);
+    match builder.build() {
+        Err(err @ ServerBuilderError::Validation(_)) => {
+            assert_eq!(err.to_string(), "port must be set when tls is on");
+        }
+        _ => panic!(),
+    }
+    builder.port(443);
+    let server = builder.build().unwrap_or_else(|_| panic!());
+    assert_eq!(server.host, "localhost");
+    assert!(server.tls);
+
+    let range = Range::builder().start(1).end(2).build();
+    assert_eq!(range.map(|range| range.end), Ok(2));
+    let range = Range::builder().start(2).end(1).build();
+    assert_eq!(
+        range.err(),
+        Some(RangeBuilderError::Validation("start is after end".to_owned()))
+    );
+}
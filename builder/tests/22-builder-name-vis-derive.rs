@@ -0,0 +1,36 @@
+// `#[builder(name = "...")]` renames the builder, and its error type follows
+// as `<name>Error`. `#[builder(vis = "...")]` overrides the visibility the
+// builder would inherit from the struct, and `#[builder(derive(...))]` adds
+// derives to the builder so it can be cloned, compared or printed.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    #[builder(name = "CmdOpts", vis = "pub(crate)", derive(Clone, Debug, PartialEq))]
+    pub struct Command {
+        pub executable: String,
+        #[builder(each = "arg")]
+        pub args: Vec<String>,
+    }
+}
+
+use config::{CmdOpts, CmdOptsError, Command};
+
+fn main() {
+    let mut fixture: CmdOpts = Command::builder();
+    fixture.arg("build".to_owned());
+
+    let mut other = fixture.clone();
+    assert_eq!(fixture, other);
+    assert!(format!("{:?}", fixture).starts_with("CmdOpts"));
+
+    assert_eq!(
+        other.build().err(),
+        Some(CmdOptsError::MissingFields(vec!["executable"]))
+    );
+    other.executable("cargo".to_owned());
+    assert_ne!(fixture, other);
+    let command = other.build().unwrap_or_else(|_| panic!());
+    assert_eq!(command.args, vec!["build"]);
+}
@@ -0,0 +1,17 @@
+// Each entry of `#[builder(derive(...))]` is checked with its own span.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(derive(Clone, Default))]
+pub struct Command {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(derive(Debug, Clone = "yes"))]
+pub struct Other {
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,51 @@
+// `#[builder(setter(prefix = "..."))]` on the struct puts a prefix before
+// every setter name, and `#[builder(setter(name = "..."))]` on a field names
+// its setter outright. The element setter of an `each` field keeps its own
+// name. `#[builder(skip)]` leaves a field out of the builder entirely; it has
+// no setter and is always built from its default.
+
+use derive_builder::Builder;
+use std::marker::PhantomData;
+
+#[derive(Builder)]
+#[builder(setter(prefix = "with_"))]
+pub struct Command {
+    #[builder(setter(name = "program"))]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(skip)]
+    pid: Option<u32>,
+    #[builder(skip, default = 3)]
+    retries: u8,
+}
+
+#[derive(Builder)]
+pub struct Cache<K, V> {
+    capacity: usize,
+    #[builder(skip)]
+    entries: Vec<(K, V)>,
+    #[builder(skip)]
+    marker: PhantomData<fn(K)>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .program("cargo".to_owned())
+        .arg("build".to_owned())
+        .with_args(vec!["test".to_owned()])
+        .with_current_dir("..".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["test"]);
+    assert_eq!(command.current_dir.unwrap(), "..");
+    assert_eq!(command.pid, None);
+    assert_eq!(command.retries, 3);
+
+    let cache = Cache::<String, u32>::builder().capacity(16).build().unwrap();
+    assert_eq!(cache.capacity, 16);
+    assert!(cache.entries.is_empty());
+}
@@ -0,0 +1,112 @@
+// `#[builder(sub_builder)]` on a field whose type also derives Builder makes
+// the outer builder hold the inner builder. It is reached either through
+// `field_mut()` or through a setter taking a closure, and the outer `build`
+// builds the inner value, reporting its errors under the path of the field.
+// Every sub builder is checked before any of them is built, so a failed build
+// leaves all of them as they were.
+//
+// The inner builder is named after the field type, `Server` is built by
+// `ServerBuilder`. One renamed with `#[builder(name = "...")]` is given as
+// `#[builder(sub_builder = "...")]`. Its setters have to take `&mut self`, so
+// it can't use the `owned` pattern (nor `typestate`), and in an `immutable`
+// builder it has to be `immutable` too.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Tls {
+    cert: String,
+    key: Option<String>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+#[derive(Builder, Debug)]
+#[builder(name = "LogOpts")]
+pub struct Logging {
+    level: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Config {
+    name: String,
+    #[builder(sub_builder)]
+    server: Server,
+    #[builder(sub_builder = "LogOpts")]
+    logging: Logging,
+}
+
+#[derive(Builder, Debug)]
+pub struct Cluster {
+    #[builder(sub_builder)]
+    primary: Tls,
+    #[builder(sub_builder)]
+    replica: Tls,
+}
+
+fn main() {
+    let mut builder = Config::builder()
+        .name("app".to_owned())
+        .server(|b| b.host("localhost".to_owned()).port(8080));
+    let err = builder.server_mut().build().unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::SubBuilder {
+            path: "tls".to_owned(),
+            message: "missing required fields: `cert`".to_owned(),
+        }
+    );
+
+    builder.server_mut().tls_mut().cert("cert.pem".to_owned());
+    let config = builder.logging(|b| b.level("debug".to_owned())).build().unwrap();
+    assert_eq!(config.name, "app");
+    assert_eq!(config.logging.level.as_deref(), Some("debug"));
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(
+        config.server.tls,
+        Tls {
+            cert: "cert.pem".to_owned(),
+            key: None,
+        }
+    );
+
+    let err = Config::builder()
+        .name("app".to_owned())
+        .server(|b| b.port(80))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`server`: missing required fields: `host`"
+    );
+
+    let err = Config::builder()
+        .name("app".to_owned())
+        .server(|b| b.host("localhost".to_owned()).port(80))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`server.tls`: missing required fields: `cert`"
+    );
+
+    let mut cluster = Cluster::builder();
+    cluster.primary(|b| b.cert("primary.pem".to_owned()));
+    for _ in 0..2 {
+        assert_eq!(
+            cluster.build().unwrap_err().to_string(),
+            "`replica`: missing required fields: `cert`"
+        );
+    }
+    cluster.replica(|b| b.cert("replica.pem".to_owned()));
+    let cluster = cluster.build().unwrap();
+    assert_eq!(cluster.primary.cert, "primary.pem");
+    assert_eq!(cluster.replica.cert, "replica.pem");
+}
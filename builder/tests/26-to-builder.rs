@@ -0,0 +1,79 @@
+// With `#[builder(to_builder)]` the builder can be created back from a value
+// with `From<T>`, and `to_builder()` does the same from a reference when the
+// struct is Clone. The builder starts with every field set, so a few setters
+// are enough to tweak an existing value. Optional fields keep `None`, `each`
+// fields keep their elements, and a sub builder field becomes the inner
+// builder again, which needs `to_builder` on the inner struct too.
+//
+// It is opt-in because `From<T>` takes the value apart, which a struct
+// implementing `Drop` doesn't allow.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(to_builder)]
+pub struct Tls {
+    cert: String,
+}
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(to_builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(skip)]
+    pid: Option<u32>,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate, to_builder)]
+pub struct Point<T>(T, T);
+
+#[derive(Builder)]
+pub struct Guard {
+    name: String,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {}
+}
+
+fn main() {
+    let base = Command {
+        executable: "cargo".to_owned(),
+        args: vec!["build".to_owned()],
+        current_dir: None,
+        pid: Some(1),
+        tls: Tls {
+            cert: "cert.pem".to_owned(),
+        },
+    };
+
+    let command = base
+        .to_builder()
+        .arg("--release".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.pid, None);
+    assert_eq!(command.tls, base.tls);
+
+    let command = CommandBuilder::from(base)
+        .executable("rustc".to_owned())
+        .tls(|b| b.cert("other.pem".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.tls.cert, "other.pem");
+
+    let point = PointBuilder::from(Point(1, 2))._1(3).build();
+    assert_eq!((point.0, point.1), (1, 3));
+
+    let guard = Guard::builder().name("lock".to_owned()).build().unwrap();
+    assert_eq!(guard.name, "lock");
+}
@@ -0,0 +1,73 @@
+// `merge(other)` overlays one builder on another: every field set in `other`
+// replaces the one in the builder, so layered configuration (defaults, then a
+// file, then the command line) can be built from generated builders alone.
+//
+// `each` fields append the elements of `other` by default, or take its whole
+// collection with `#[builder(merge = "replace")]`. Sub builders are merged
+// field by field.
+//
+// A field named `merge` (or `missing_fields`) keeps its setter, and its
+// builder goes without that method. It still merges as a sub builder.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Tls {
+    cert: String,
+    key: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", merge = "replace")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+#[derive(Builder, Debug)]
+pub struct Conflict {
+    merge: Option<String>,
+    missing_fields: Option<u32>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Resolve {
+    #[builder(sub_builder)]
+    conflict: Conflict,
+}
+
+fn main() {
+    let mut defaults = Command::builder();
+    defaults
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .env("RUST_LOG=info".to_owned())
+        .current_dir("..".to_owned())
+        .tls(|b| b.cert("default.pem".to_owned()).key("default.key".to_owned()));
+
+    let mut cli = Command::builder();
+    cli.arg("--release".to_owned())
+        .env("RUST_LOG=debug".to_owned())
+        .tls(|b| b.cert("cli.pem".to_owned()));
+
+    let command = defaults.merge(cli).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.env, vec!["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir.unwrap(), "..");
+    assert_eq!(command.tls.cert, "cli.pem");
+    assert_eq!(command.tls.key.unwrap(), "default.key");
+
+    let mut base = Resolve::builder();
+    base.conflict(|b| b.merge("ours".to_owned()).missing_fields(1));
+    let mut theirs = Resolve::builder();
+    theirs.conflict(|b| b.missing_fields(2));
+    let resolve = base.merge(theirs).build().unwrap();
+    assert_eq!(resolve.conflict.merge.as_deref(), Some("ours"));
+    assert_eq!(resolve.conflict.missing_fields, Some(2));
+}
@@ -0,0 +1,53 @@
+// Every builder can be inspected without calling `build`. `is_x_set()` tells
+// whether a field was set, `clear_x()` forgets it again, and
+// `missing_fields()` lists the required fields `build` would report.
+//
+// A read-only getter `x()` returning `Option<&T>` is generated too, unless a
+// setter already has that name, which is the case without a setter prefix.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(setter(prefix = "with_"))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Job {
+    name: String,
+    #[builder(default)]
+    retries: u32,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert_eq!(builder.missing_fields(), vec!["executable"]);
+    assert!(!builder.is_executable_set());
+    assert_eq!(builder.executable(), None);
+
+    builder
+        .with_executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .with_current_dir("..".to_owned());
+    assert!(builder.missing_fields().is_empty());
+    assert_eq!(builder.executable().unwrap(), "cargo");
+    assert_eq!(builder.args().unwrap(), &vec!["build".to_owned()]);
+    assert_eq!(builder.current_dir().map(String::as_str), Some(".."));
+
+    builder.clear_executable().clear_args();
+    assert!(!builder.is_executable_set());
+    assert!(!builder.is_args_set());
+    assert!(builder.is_current_dir_set());
+    assert!(builder.build().is_err());
+
+    let mut job = Job::builder();
+    job.name("nightly".to_owned());
+    assert!(job.is_name_set());
+    assert!(!job.is_retries_set());
+    job.clear_name();
+    assert_eq!(job.missing_fields(), vec!["name"]);
+}
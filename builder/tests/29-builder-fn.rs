@@ -0,0 +1,55 @@
+// `#[builder]` on a function turns its parameters into a builder: calling the
+// function returns the builder, and `call()` runs the function once every
+// required argument is set. Parameters are classified like fields, so
+// `Option` arguments may be left out and `#[builder(each = "...")]` adds one
+// element at a time.
+//
+// Methods work the same inside an `impl` block carrying `#[builder]` too.
+// Arguments are tracked like `#[builder(typestate)]`, so forgetting one is a
+// compile error rather than a panic.
+
+use derive_builder::builder;
+
+#[builder]
+fn connect(host: String, port: Option<u16>, #[builder(each = "opt")] opts: Vec<String>) -> String {
+    format!("{}:{} {}", host, port.unwrap_or(80), opts.join(","))
+}
+
+#[builder(setter(into))]
+fn parse<T: std::str::FromStr>(input: String, #[builder(default)] radix: u32) -> Option<T> {
+    let _ = radix;
+    input.parse().ok()
+}
+
+pub struct Client {
+    name: String,
+}
+
+#[builder]
+impl Client {
+    #[builder]
+    pub fn new(name: String) -> Self {
+        Client { name }
+    }
+
+    #[builder]
+    fn request(&self, path: String, retries: Option<u32>) -> String {
+        format!("{} {} {}", self.name, path, retries.unwrap_or(0))
+    }
+}
+
+fn main() {
+    let addr = connect()
+        .host("localhost".to_owned())
+        .opt("tls".to_owned())
+        .opt("nodelay".to_owned())
+        .call();
+    assert_eq!(addr, "localhost:80 tls,nodelay");
+
+    let n = parse::<u8>().input("42").call();
+    assert_eq!(n, Some(42));
+
+    let client = Client::new().name("api".to_owned()).call();
+    let response = client.request().path("/users".to_owned()).retries(3).call();
+    assert_eq!(response, "api /users 3");
+}
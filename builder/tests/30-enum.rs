@@ -0,0 +1,77 @@
+// On an enum, every variant with fields gets its own builder, started by a
+// snake case method on the enum: `Shape::circle_builder()` builds
+// `Shape::Circle` through a `ShapeCircleBuilder`. Fields are handled exactly
+// like struct fields, and `#[builder(..)]` on the enum applies to every
+// variant while `#[builder(..)]` on a variant only applies to that one.
+//
+// A run of capitals is one word, `Request::HTTPGet` is started by
+// `Request::http_get_builder()`.
+//
+// A variant builder takes every generic parameter of the enum, including the
+// ones its variant doesn't use.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(setter(into))]
+pub enum Event {
+    Message {
+        from: String,
+        #[builder(each = "line")]
+        lines: Vec<String>,
+        reply_to: Option<u64>,
+    },
+    #[builder(name = "KeyPress", pattern = "owned")]
+    KeyPress(char, #[builder(default)] u8),
+    Quit,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape<'a, T> {
+    Circle { r: u32 },
+    Poly { pts: Vec<T> },
+    Label { text: &'a str },
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Request {
+    HTTPGet { url: String },
+    ToURL(String),
+}
+
+fn main() {
+    let event = Event::message_builder()
+        .from("alice")
+        .line("hello")
+        .line("world")
+        .build()
+        .unwrap();
+    assert_eq!(
+        event,
+        Event::Message {
+            from: "alice".to_owned(),
+            lines: vec!["hello".to_owned(), "world".to_owned()],
+            reply_to: None,
+        }
+    );
+
+    let err = Event::message_builder().build().unwrap_err();
+    assert_eq!(err, EventMessageBuilderError::MissingFields(vec!["from"]));
+
+    let builder: KeyPress = Event::key_press_builder();
+    let event = builder._0('q').build().unwrap();
+    assert_eq!(event, Event::KeyPress('q', 0));
+    assert_ne!(event, Event::Quit);
+
+    let circle: Shape<u8> = Shape::circle_builder().r(2).build().unwrap();
+    assert_eq!(circle, Shape::Circle { r: 2 });
+    let poly = Shape::<u8>::poly_builder().pts(vec![1, 2]).build().unwrap();
+    assert_eq!(poly, Shape::Poly { pts: vec![1, 2] });
+    let label = Shape::<()>::label_builder().text("hi").build().unwrap();
+    assert_eq!(label, Shape::Label { text: "hi" });
+
+    let get = Request::http_get_builder().url("/".to_owned()).build().unwrap();
+    assert_eq!(get, Request::HTTPGet { url: "/".to_owned() });
+    let to = Request::to_url_builder()._0("/".to_owned()).build().unwrap();
+    assert_eq!(to, Request::ToURL("/".to_owned()));
+}
@@ -0,0 +1,120 @@
+// `#[builder(env = "...")]` lets `build` fall back to an environment variable
+// parsed with `FromStr` when the setter of the field wasn't called. A setter
+// still wins over the variable, and `#[builder(default)]` only applies when
+// neither is there. A variable that doesn't parse fails the build with
+// `Env`, naming the variable.
+//
+// The variables are parsed before any sub builder is built, also those of
+// the sub builders, so a failure leaves every builder as it was. Each
+// variable is read once per `build`, so one removed while building doesn't
+// count as set.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Service {
+    #[builder(env = "BUILDER_TEST_HOST")]
+    host: String,
+    #[builder(env = "BUILDER_TEST_PORT", default = 80)]
+    port: u16,
+    #[builder(env = "BUILDER_TEST_WORKERS")]
+    workers: Option<usize>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Tls {
+    cert: String,
+    #[builder(env = "BUILDER_TEST_TLS_PORT", default = 443)]
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    #[builder(sub_builder)]
+    tls: Tls,
+    #[builder(sub_builder)]
+    admin: Tls,
+    #[builder(env = "BUILDER_TEST_RETRIES")]
+    retries: u8,
+}
+
+#[derive(Builder, Debug)]
+#[builder(build_fn(validate = "remove_job"))]
+pub struct Job {
+    #[builder(env = "BUILDER_TEST_JOB")]
+    name: String,
+}
+
+fn remove_job(_: &JobBuilder) -> Result<(), String> {
+    remove_var("BUILDER_TEST_JOB");
+    Ok(())
+}
+
+// the test is single threaded, which is what `set_var` and `remove_var` require
+#[allow(unused_unsafe)]
+fn set_var(key: &str, value: &str) {
+    unsafe { std::env::set_var(key, value) }
+}
+
+#[allow(unused_unsafe)]
+fn remove_var(key: &str) {
+    unsafe { std::env::remove_var(key) }
+}
+
+fn main() {
+    let err = Service::builder().build().unwrap_err();
+    assert_eq!(err, ServiceBuilderError::MissingFields(vec!["host"]));
+
+    set_var("BUILDER_TEST_HOST", "localhost");
+    let service = Service::builder().build().unwrap();
+    assert_eq!(service.host, "localhost");
+    assert_eq!(service.port, 80);
+    assert_eq!(service.workers, None);
+
+    set_var("BUILDER_TEST_PORT", "8080");
+    set_var("BUILDER_TEST_WORKERS", "4");
+    let service = Service::builder().host("example.com".to_owned()).build().unwrap();
+    assert_eq!(service.host, "example.com");
+    assert_eq!(service.port, 8080);
+    assert_eq!(service.workers, Some(4));
+
+    set_var("BUILDER_TEST_PORT", "http");
+    let mut builder = Service::builder();
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "environment variable `BUILDER_TEST_PORT`: invalid digit found in string"
+    );
+    assert!(builder.port(443).build().is_ok());
+
+    set_var("BUILDER_TEST_RETRIES", "many");
+    let mut builder = Server::builder();
+    builder
+        .tls(|b| b.cert("tls.pem".to_owned()))
+        .admin(|b| b.cert("admin.pem".to_owned()));
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "environment variable `BUILDER_TEST_RETRIES`: invalid digit found in string"
+    );
+    builder.retries(3);
+
+    set_var("BUILDER_TEST_TLS_PORT", "https");
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`tls`: environment variable `BUILDER_TEST_TLS_PORT`: invalid digit found in string"
+    );
+
+    set_var("BUILDER_TEST_TLS_PORT", "8443");
+    let server = builder.build().unwrap();
+    assert_eq!(server.tls.cert, "tls.pem");
+    assert_eq!(server.admin.cert, "admin.pem");
+    assert_eq!(server.admin.port, 8443);
+    assert_eq!(server.retries, 3);
+
+    set_var("BUILDER_TEST_JOB", "nightly");
+    let job = Job::builder().build().unwrap();
+    assert_eq!(job.name, "nightly");
+    assert_eq!(Job::builder().build().unwrap_err(), JobBuilderError::MissingFields(vec!["name"]));
+}
@@ -0,0 +1,66 @@
+// Fields can be put in a group with `#[builder(group = "...")]`. With
+// `exclusive` on one of them, at most one field of the group may be set; with
+// `required`, exactly one has to be. `build` reports the setters involved,
+// `GroupConflict` when too many were called and `GroupMissing` when none was.
+//
+// Grouped fields may be left unset, so they have to be `Option`, `each` or
+// have a default.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(setter(prefix = "with_"))]
+pub struct Connection {
+    host: String,
+    #[builder(group = "auth", required)]
+    password: Option<String>,
+    #[builder(group = "auth")]
+    token: Option<String>,
+    #[builder(group = "auth", each = "key_file")]
+    key_files: Vec<String>,
+    #[builder(group = "compression", exclusive)]
+    gzip: Option<bool>,
+    #[builder(group = "compression")]
+    zstd: Option<bool>,
+}
+
+fn main() {
+    let mut builder = Connection::builder();
+    builder.with_host("db".to_owned());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ConnectionBuilderError::GroupMissing {
+            group: "auth",
+            fields: vec!["with_password", "with_token", "with_key_files"],
+        }
+    );
+
+    builder
+        .with_password("hunter2".to_owned())
+        .key_file("id_rsa".to_owned());
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "only one of `with_password`, `with_key_files` can be set in group `auth`"
+    );
+
+    builder.clear_key_files();
+    let connection = builder.build().unwrap();
+    assert_eq!(connection.password.unwrap(), "hunter2");
+    assert_eq!(connection.gzip, None);
+
+    let err = Connection::builder()
+        .with_host("db".to_owned())
+        .with_token("t0k3n".to_owned())
+        .with_gzip(true)
+        .with_zstd(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ConnectionBuilderError::GroupConflict {
+            group: "compression",
+            fields: vec!["with_gzip", "with_zstd"],
+        }
+    );
+}
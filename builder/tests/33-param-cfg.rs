@@ -0,0 +1,40 @@
+// rustc removes the fields `#[cfg(...)]` compiles out before a derive sees
+// them, but `#[builder]` on a function gets its parameters as written. A
+// `#[cfg(...)]` parameter is repeated on everything generated for it: the
+// slot in the builder, its setters and getters, and the argument `call()`
+// passes on. A required parameter compiled out doesn't hold `call()` back.
+
+use derive_builder::builder;
+
+#[builder]
+fn connect(
+    host: String,
+    #[cfg(any())] tls: bool,
+    #[cfg(all())] port: u16,
+    #[cfg(any())]
+    #[builder(each = "cert")]
+    certs: Vec<String>,
+    #[cfg(all())] timeout: Option<u32>,
+) -> String {
+    format!("{}:{} {:?}", host, port, timeout)
+}
+
+pub struct Client;
+
+#[builder]
+impl Client {
+    #[builder]
+    fn request(&self, path: String, #[cfg(any())] retries: u32) -> String {
+        format!("GET {}", path)
+    }
+}
+
+fn main() {
+    let url = connect().host("localhost".to_owned()).port(8080).call();
+    assert_eq!(url, "localhost:8080 None");
+
+    let url = connect().port(80).timeout(5).host("example.com".to_owned()).call();
+    assert_eq!(url, "example.com:80 Some(5)");
+
+    assert_eq!(Client.request().path("/".to_owned()).call(), "GET /");
+}
@@ -0,0 +1,48 @@
+// Fields marked `#[builder(start_fn)]` become parameters of `builder(..)`
+// instead of getting setters, so they can't be forgotten. The builder then
+// has no `Default` impl, which would make one without them.
+//
+// With `#[builder(typestate)]` these fields already count as set, and the
+// same works for the parameters of a `#[builder]` function.
+
+use derive_builder::{builder, Builder};
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    #[builder(start_fn)]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Request {
+    #[builder(start_fn)]
+    method: &'static str,
+    path: String,
+    body: Option<String>,
+}
+
+#[builder]
+fn connect(#[builder(start_fn)] host: String, port: Option<u16>) -> String {
+    format!("{}:{}", host, port.unwrap_or(80))
+}
+
+fn main() {
+    let command = Command::builder("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir, None);
+
+    let request = Request::builder("GET").path("/".to_owned()).build();
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/");
+    assert_eq!(request.body, None);
+
+    assert_eq!(connect("localhost".to_owned()).port(8080).call(), "localhost:8080");
+}
@@ -0,0 +1,52 @@
+// `#[builder(debug)]` gives the builder a `Debug` impl for logging a builder
+// whose `build()` failed. Unset fields print as `<unset>`, `each` collections
+// print with their length, and `#[builder(redact)]` hides a field's value.
+// A redacted sub builder is hidden as a whole, so it needs no `Debug` itself.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(debug)]
+pub struct Connection<T> {
+    host: String,
+    port: Option<u16>,
+    #[builder(redact)]
+    password: String,
+    #[builder(each = "tag")]
+    tags: Vec<T>,
+}
+
+#[derive(Builder)]
+pub struct Credentials {
+    user: String,
+    key: String,
+}
+
+#[derive(Builder)]
+#[builder(debug)]
+pub struct Server {
+    host: String,
+    #[builder(sub_builder, redact)]
+    credentials: Credentials,
+}
+
+fn main() {
+    let mut builder = Connection::<&str>::builder();
+    assert_eq!(
+        format!("{:?}", builder),
+        r#"ConnectionBuilder { host: <unset>, port: <unset>, password: <unset>, tags: <unset> }"#
+    );
+
+    builder.host("localhost".to_owned()).password("hunter2".to_owned()).tag("a").tag("b");
+    assert_eq!(
+        format!("{:?}", builder),
+        r#"ConnectionBuilder { host: "localhost", port: <unset>, password: <redacted>, tags: ["a", "b"] (2 items) }"#
+    );
+
+    let mut builder = Server::builder();
+    builder.host("h".to_owned()).credentials(|b| b.user("admin".to_owned()).key("secret".to_owned()));
+    assert_eq!(
+        format!("{:?}", builder),
+        r#"ServerBuilder { host: "h", credentials: <redacted> }"#
+    );
+}
@@ -0,0 +1,12 @@
+// `#[builder(debug)]` and `#[builder(derive(Debug))]` would both implement
+// `Debug` for the builder, so asking for both is an error.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(debug, derive(Debug))]
+pub struct Command {
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,62 @@
+// `#[builder(crate_root = alloc)]` makes the generated code take `Vec`,
+// `String` and `format!` from `::alloc` instead of `::std`, so the builder
+// works in `no_std` crates that have `extern crate alloc`. `alloc::vec::Vec`
+// and the `alloc::collections` types are recognized for `each` like their
+// `std` reexports.
+//
+// std is only linked for the test binary, under a name `::std` paths in the
+// generated code wouldn't find.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as _;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(crate_root = alloc, debug, build_fn(validate = Self::validate))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: alloc::vec::Vec<String>,
+    #[builder(each = "env")]
+    env: BTreeMap<String, String>,
+}
+
+impl CommandBuilder {
+    fn validate(&self) -> Result<(), String> {
+        match &self.executable {
+            Some(executable) if executable.is_empty() => Err("executable is empty".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert_eq!(
+        builder.build().err(),
+        Some(CommandBuilderError::MissingFields(alloc::vec!["executable"]))
+    );
+
+    builder.executable(String::new());
+    assert_eq!(
+        builder.build().err(),
+        Some(CommandBuilderError::Validation("executable is empty".to_string()))
+    );
+
+    builder
+        .executable("cargo".to_string())
+        .arg("build".to_string())
+        .env("RUST_LOG".to_string(), "info".to_string());
+    assert_eq!(
+        alloc::format!("{:?}", builder),
+        r#"CommandBuilder { executable: "cargo", args: ["build"] (1 items), env: {"RUST_LOG": "info"} (1 items) }"#
+    );
+    let command = builder.build().unwrap();
+    assert_eq!(command.args, ["build"]);
+    assert_eq!(command.env["RUST_LOG"], "info");
+}
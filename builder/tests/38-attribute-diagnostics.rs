@@ -0,0 +1,37 @@
+// Every key of `#[builder(..)]` is checked on its own, so the error points at
+// the key that is wrong: a key given twice on the same item is an error, and
+// a misspelled key suggests the one that was probably meant. `setter(..)` may
+// be split across attributes, each of its keys is checked on its own too.
+// A key of the struct used on a field, or the other way around, says where it
+// goes, and an unknown key lists the ones that are valid there.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(patern = "owned")]
+pub struct Command {
+    #[builder(each = "arg", default, each = "argument")]
+    args: Vec<String>,
+    #[builder(defualt)]
+    env: Vec<String>,
+    #[builder(setter(into))]
+    #[builder(setter(prefix = "with"))]
+    current_dir: Option<String>,
+    #[builder(setter(into))]
+    #[builder(setter(prefix = "with", into))]
+    work_dir: Option<String>,
+    #[builder(setter(intoo))]
+    executable: String,
+    #[builder(frobnicate)]
+    timeout: u32,
+    #[builder(pattern = "owned")]
+    retries: u32,
+}
+
+#[derive(Builder)]
+#[builder(each = "arg")]
+pub struct Script {
+    args: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+// A `mutable` builder moves its values out before `validate_built` sees the
+// built value, so a failed validation would leave it empty. The combination
+// is rejected.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(build_fn(validate_built = Range::check))]
+pub struct Range {
+    start: u32,
+    end: u32,
+}
+
+impl Range {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.start > self.end {
+            Err("start is after end")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+// The setters of an `owned` builder take `self`, so it can't be updated or
+// built through the `&mut` an outer builder holds it behind. Using one as a
+// sub builder is an error pointing at `sub_builder`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Tls {
+    cert: String,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+fn main() {}
@@ -0,0 +1,57 @@
+// Raw identifiers work as field names. The generated methods use the name
+// without `r#`, so `r#type` gets `clear_type()` and `is_type_set()`, and
+// errors name the field `type`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(debug)]
+pub struct Inner {
+    r#match: String,
+}
+
+#[derive(Builder, Debug)]
+#[builder(debug)]
+pub struct Item {
+    r#type: String,
+    #[builder(each = "r#in")]
+    r#in: Vec<String>,
+    #[builder(env = "RAW_IDENT_LOOP")]
+    r#loop: Option<u32>,
+    #[builder(sub_builder)]
+    r#ref: Inner,
+    #[builder(setter(prefix = "with_", try_into))]
+    r#mut: u16,
+}
+
+fn main() {
+    let mut builder = Item::builder();
+    assert_eq!(builder.missing_fields(), vec!["type", "mut"]);
+    builder
+        .r#type("t".to_owned())
+        .r#in("a".to_owned())
+        .extend_in(vec!["b".to_owned()])
+        .r#ref(|b| b.r#match("m".to_owned()));
+    builder.try_with_mut(7u64).unwrap();
+    assert!(builder.is_type_set());
+    builder.clear_type();
+    assert!(!builder.is_type_set());
+    builder.r#type("t".to_owned());
+    builder.ref_mut().r#match("n".to_owned());
+    assert_eq!(
+        format!("{:?}", builder),
+        r#"ItemBuilder { type: "t", in: ["a", "b"] (2 items), loop: <unset>, ref: InnerBuilder { match: "n" }, mut: 7 }"#
+    );
+    let item = builder.build().unwrap();
+    assert_eq!(item.r#in, vec!["a", "b"]);
+    assert_eq!(item.r#ref.r#match, "n");
+    assert_eq!(item.r#mut, 7);
+
+    let err = Item::builder().build().unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: `type`, `mut`");
+
+    let mut builder = Item::builder();
+    builder.r#type("t".to_owned()).with_mut(1).r#ref(|b| b);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err.to_string(), "`ref`: missing required fields: `match`");
+}
@@ -0,0 +1,65 @@
+// Doc comments of a field are copied onto its setters, and every other
+// public item the derive generates is documented, so a crate denying
+// `missing_docs` can derive builders for its documented types. Parameters
+// can't have doc comments, the setters of a `#[builder]` function name the
+// parameter they set.
+
+#![deny(missing_docs)]
+
+//! Setter docs.
+
+use derive_builder::{builder, Builder};
+
+/// A command to run.
+#[derive(Builder)]
+#[builder(to_builder, setter(try_into), build_fn(validate = "check"))]
+pub struct Command {
+    /// the program to run
+    executable: String,
+    /// the arguments
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    /// where to run it
+    current_dir: Option<String>,
+    /// the tls settings
+    #[builder(sub_builder)]
+    tls: Tls,
+    /// the exit code expected
+    code: u8,
+    /// where the output goes
+    #[builder(env = "COMMAND_LOG", group = "output", exclusive)]
+    log_file: Option<String>,
+    /// whether to log to syslog
+    #[builder(group = "output")]
+    syslog: Option<bool>,
+}
+
+fn check(_: &CommandBuilder) -> Result<(), String> {
+    Ok(())
+}
+
+/// TLS settings.
+#[derive(Builder, Clone)]
+#[builder(to_builder)]
+pub struct Tls {
+    /// the certificate
+    cert: String,
+}
+
+/// A shape.
+#[derive(Builder)]
+pub enum Shape {
+    /// a circle
+    Circle {
+        /// the radius
+        r: u32,
+    },
+}
+
+/// Connects to `host`.
+#[builder]
+pub fn connect(host: String) -> String {
+    host
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+// A group only takes fields the builder can leave unset. A skipped field is
+// never set and a sub builder always is, so neither can be in a group.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Tls {
+    cert: String,
+}
+
+#[derive(Builder)]
+pub struct Connection {
+    #[builder(group = "auth", required)]
+    password: Option<String>,
+    #[builder(skip, group = "auth")]
+    token: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(group = "security", exclusive)]
+    plain: Option<bool>,
+    #[builder(sub_builder, group = "security")]
+    tls: Tls,
+}
+
+fn main() {}
@@ -10,4 +10,38 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-build-error.rs");
+    t.pass("tests/11-build-pattern.rs");
+    t.pass("tests/12-field-default.rs");
+    t.pass("tests/13-generics.rs");
+    t.pass("tests/14-tuple-struct.rs");
+    t.pass("tests/15-typestate.rs");
+    t.compile_fail("tests/16-typestate-missing-field.rs");
+    t.pass("tests/17-setter-into.rs");
+    t.pass("tests/18-each-collections.rs");
+    t.pass("tests/19-each-full-setter.rs");
+    t.compile_fail("tests/20-duplicate-method.rs");
+    t.pass("tests/21-validate.rs");
+    t.pass("tests/22-builder-name-vis-derive.rs");
+    t.compile_fail("tests/23-builder-derive-invalid.rs");
+    t.pass("tests/24-setter-name-skip.rs");
+    t.pass("tests/25-sub-builder.rs");
+    t.pass("tests/26-to-builder.rs");
+    t.pass("tests/27-merge.rs");
+    t.pass("tests/28-introspection.rs");
+    t.pass("tests/29-builder-fn.rs");
+    t.pass("tests/30-enum.rs");
+    t.pass("tests/31-env.rs");
+    t.pass("tests/32-field-groups.rs");
+    t.pass("tests/33-param-cfg.rs");
+    t.pass("tests/34-start-fn.rs");
+    t.pass("tests/35-builder-debug.rs");
+    t.compile_fail("tests/36-builder-debug-derive.rs");
+    t.pass("tests/37-crate-root-alloc.rs");
+    t.compile_fail("tests/38-attribute-diagnostics.rs");
+    t.compile_fail("tests/39-validate-built-mutable.rs");
+    t.compile_fail("tests/40-sub-builder-owned.rs");
+    t.pass("tests/41-raw-identifiers.rs");
+    t.pass("tests/42-setter-docs.rs");
+    t.compile_fail("tests/43-group-invalid.rs");
 }
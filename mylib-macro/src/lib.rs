@@ -73,6 +73,8 @@ pub fn types_vec() -> Vec<Path> {
         syn::parse2::<Path>(quote!(Vec)).unwrap(),
         syn::parse2::<Path>(quote!(::std::vec::Vec)).unwrap(),
         syn::parse2::<Path>(quote!(std::vec::Vec)).unwrap(),
+        syn::parse2::<Path>(quote!(::alloc::vec::Vec)).unwrap(),
+        syn::parse2::<Path>(quote!(alloc::vec::Vec)).unwrap(),
     ]
 }
 
@@ -80,6 +82,88 @@ pub fn is_vec(ty: &Type) -> Option<&Type> {
     is_types_one_param(ty, types_vec().iter())
 }
 
+// all type arguments, `HashMap<K, V, S>` gives `[K, V, S]`
+pub fn is_types_params<'a, 'b>(
+    ty: &'a Type,
+    mut types: impl Iterator<Item = &'b Path>,
+) -> Option<Vec<&'a Type>> {
+    if let Type::Path(TypePath { path, qself: None }) = ty
+        && let Some(last) = path.segments.last()
+        && let PathArguments::AngleBracketed(ref tps) = last.arguments
+        && types.any(|t| is_path(path, t))
+    {
+        Some(
+            tps.args
+                .iter()
+                .filter_map(|arg| {
+                    if let GenericArgument::Type(ty) = arg {
+                        Some(ty)
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        )
+    } else {
+        None
+    }
+}
+
+// `HashMap`, `::std::collections::HashMap`, `::std::collections::hash_map::HashMap`, ...
+// `roots` are the crates exporting them, `["std", "alloc"]` for all but the hash based ones
+fn types_std_collections(roots: &[&str], names: &[(&str, &str)]) -> Vec<Path> {
+    let mut types = vec![];
+    for (name, module) in names {
+        let id = format_ident!("{}", name);
+        let module = format_ident!("{}", module);
+        types.push(syn::parse2::<Path>(quote!(#id)).unwrap());
+        for root in roots {
+            let root = format_ident!("{}", root);
+            types.push(syn::parse2::<Path>(quote!(::#root::collections::#id)).unwrap());
+            types.push(syn::parse2::<Path>(quote!(#root::collections::#id)).unwrap());
+            types.push(syn::parse2::<Path>(quote!(::#root::collections::#module::#id)).unwrap());
+            types.push(syn::parse2::<Path>(quote!(#root::collections::#module::#id)).unwrap());
+        }
+    }
+    types
+}
+
+pub fn types_map() -> Vec<Path> {
+    let mut types = types_std_collections(&["std"], &[("HashMap", "hash_map")]);
+    types.extend(types_std_collections(&["std", "alloc"], &[("BTreeMap", "btree_map")]));
+    types
+}
+
+// `HashMap<K, V>`, `BTreeMap<K, V>`
+pub fn is_map(ty: &Type) -> Option<(&Type, &Type)> {
+    match is_types_params(ty, types_map().iter())?.as_slice() {
+        [k, v, ..] => Some((k, v)),
+        _ => None,
+    }
+}
+
+pub fn types_collection() -> Vec<Path> {
+    let mut types = types_vec();
+    types.extend(types_std_collections(&["std"], &[("HashSet", "hash_set")]));
+    types.extend(types_std_collections(
+        &["std", "alloc"],
+        &[
+            ("VecDeque", "vec_deque"),
+            ("LinkedList", "linked_list"),
+            ("BTreeSet", "btree_set"),
+            ("BinaryHeap", "binary_heap"),
+        ],
+    ));
+    types
+}
+
+// `Vec<T>`, `VecDeque<T>`, `HashSet<T>`, ... the element type
+pub fn is_collection(ty: &Type) -> Option<&Type> {
+    is_types_params(ty, types_collection().iter())?
+        .first()
+        .copied()
+}
+
 // will ignore `PhantomData<T>`, `*mut T`, `*const T`
 pub fn contains_generic_param(ty: &Type, gpid: &Ident) -> bool {
     match ty {